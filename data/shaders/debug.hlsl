@@ -0,0 +1,33 @@
+struct FrameUniforms {
+    float4x4 view_projection;
+    float4x4 inverse_view_projection;
+    float4 camera_position;
+    float4 ambient;
+};
+
+ConstantBuffer<FrameUniforms> frame : register(b0, space0);
+
+struct PsInput {
+    float4 position : SV_POSITION;
+    float4 color : COLOR;
+};
+
+struct PsOutput {
+    float4 color : SV_TARGET0;
+    uint object_id : SV_TARGET1;
+};
+
+PsInput vs_main(float3 position : POSITION, float4 color : COLOR) {
+    PsInput result;
+    result.position = mul(frame.view_projection, float4(position, 1.0));
+    result.color = color;
+    return result;
+}
+
+// object id writes are masked out, debug lines can't be picked
+PsOutput fs_main(PsInput input) {
+    PsOutput output;
+    output.color = input.color;
+    output.object_id = 0;
+    return output;
+}
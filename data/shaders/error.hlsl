@@ -0,0 +1,34 @@
+// Stand-in for shaders that failed to compile. The vertex stage expects the
+// mesh vertex layout and the material pipeline layout, the fragment stage
+// works with any vertex stage.
+
+struct FrameUniforms {
+    float4x4 view_projection;
+    float4x4 inverse_view_projection;
+    float4 camera_position;
+    float4 ambient;
+};
+
+struct ObjectUniforms {
+    float4x4 transform;
+    uint object_id;
+};
+
+ConstantBuffer<FrameUniforms> frame : register(b0, space0);
+ConstantBuffer<ObjectUniforms> object : register(b0, space2);
+
+struct PsOutput {
+    float4 color : SV_TARGET0;
+    uint object_id : SV_TARGET1;
+};
+
+float4 vs_main(float3 position : POSITION) : SV_POSITION {
+    return mul(frame.view_projection, mul(object.transform, float4(position, 1.0)));
+}
+
+PsOutput fs_main() {
+    PsOutput output;
+    output.color = float4(1.0, 0.0, 1.0, 1.0);
+    output.object_id = 0;
+    return output;
+}
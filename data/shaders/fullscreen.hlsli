@@ -0,0 +1,20 @@
+#ifndef FULLSCREEN_HLSLI
+#define FULLSCREEN_HLSLI
+
+struct FullscreenVertex {
+    float4 position;
+    float2 uv;
+};
+
+// One triangle covering the whole target, drawn with 3 vertices and no
+// vertex buffer. uv is (0, 0) at the top left corner of the target.
+FullscreenVertex fullscreen_triangle(uint vertex_id, float depth) {
+    float2 uv = float2((vertex_id << 1) & 2, vertex_id & 2);
+
+    FullscreenVertex result;
+    result.position = float4(uv * float2(2.0, -2.0) + float2(-1.0, 1.0), depth, 1.0);
+    result.uv = uv;
+    return result;
+}
+
+#endif
@@ -1,8 +1,17 @@
-// [[vk::push_constant]]
-// struct PushConstants {
-//     float4x4 view_projection;
-//     float4x4 transform;
-// } push_constants;
+struct FrameUniforms {
+    float4x4 view_projection;
+    float4x4 inverse_view_projection;
+    float4 camera_position;
+    float4 ambient;
+};
+
+struct ObjectUniforms {
+    float4x4 transform;
+    uint object_id;
+};
+
+ConstantBuffer<FrameUniforms> frame : register(b0, space0);
+ConstantBuffer<ObjectUniforms> object : register(b0, space2);
 
 struct PsInput {
     float4 position : SV_POSITION;
@@ -10,31 +19,37 @@ struct PsInput {
     float3 normal : NORMAL;
 };
 
+struct PsOutput {
+    float4 color : SV_TARGET0;
+    uint object_id : SV_TARGET1;
+};
+
 PsInput vs_main(
     float3 position : POSITION,
     float3 normal : NORMAL,
     float2 texcoord : TEXCOORD
 ) {
     PsInput result;
-    result.position = float4(position, 1.0);
-    result.normal = normal;
+    result.position = mul(frame.view_projection, mul(object.transform, float4(position, 1.0)));
+    // fine as long as transforms don't scale unevenly
+    result.normal = mul((float3x3)object.transform, normal);
     result.texcoord = texcoord;
-    // result.position = mul(push_constants.view_projection, mul(push_constants.transform, float4(position, 1.0)));
-    // result.texcoord = texcoord;
-    // result.normal = mul((float4x3)push_constants.transform, normal);
     return result;
 }
 
-float4 fs_main(PsInput input) : SV_TARGET {
+PsOutput fs_main(PsInput input) {
     float3 sun_dir = normalize(float3(0.7, 0.8, 0.3));
     float3 sun_color = float3(1.0, 1.0, 1.0);
 
     float3 albedo = float3(1.0, 1.0, 1.0);
-    float env = 0.4;
 
-    float n_dot_l = dot(input.normal, normalize(sun_dir));
+    float n_dot_l = saturate(dot(normalize(input.normal), sun_dir));
 
-    float3 shaded = saturate(env + albedo * sun_color * (saturate(n_dot_l) - 0.5 * env));
+    // ambient is premultiplied by its intensity, exposure is in w
+    float3 shaded = albedo * (frame.ambient.rgb + sun_color * n_dot_l);
 
-    return float4(shaded, 1.0);
+    PsOutput output;
+    output.color = float4(shaded * frame.ambient.w, 1.0);
+    output.object_id = object.object_id;
+    return output;
 }
@@ -0,0 +1,43 @@
+#include "fullscreen.hlsli"
+
+struct FrameUniforms {
+    float4x4 view_projection;
+    float4x4 inverse_view_projection;
+    float4 camera_position;
+    float4 ambient;
+};
+
+ConstantBuffer<FrameUniforms> frame : register(b0, space0);
+
+TextureCube environment : register(t0, space1);
+SamplerState environment_sampler : register(s1, space1);
+
+struct PsInput {
+    float4 position : SV_POSITION;
+    float3 direction : DIRECTION;
+};
+
+struct PsOutput {
+    float4 color : SV_TARGET0;
+    uint object_id : SV_TARGET1;
+};
+
+PsInput vs_main(uint vertex_id : SV_VertexID) {
+    // on the far plane
+    float4 position = fullscreen_triangle(vertex_id, 1.0).position;
+    float4 world_position = mul(frame.inverse_view_projection, position);
+
+    PsInput result;
+    result.position = position;
+    result.direction = world_position.xyz / world_position.w - frame.camera_position.xyz;
+    return result;
+}
+
+PsOutput fs_main(PsInput input) {
+    float3 color = environment.Sample(environment_sampler, normalize(input.direction)).rgb;
+
+    PsOutput output;
+    output.color = float4(color * frame.ambient.w, 1.0);
+    output.object_id = 0;
+    return output;
+}
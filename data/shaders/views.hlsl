@@ -0,0 +1,18 @@
+#include "fullscreen.hlsli"
+
+// Scene color of all views, the size of the window.
+Texture2D<float4> views : register(t0, space0);
+
+struct PsInput {
+    float4 position : SV_POSITION;
+};
+
+PsInput vs_main(uint vertex_id : SV_VertexID) {
+    PsInput result;
+    result.position = fullscreen_triangle(vertex_id, 0.0).position;
+    return result;
+}
+
+float4 fs_main(PsInput input) : SV_TARGET {
+    return views.Load(int3(input.position.xy, 0));
+}
@@ -6,9 +6,11 @@ use uuid::Uuid;
 
 mod model;
 mod shader;
+mod texture;
 
 pub use self::model::*;
 pub use self::shader::*;
+pub use self::texture::*;
 
 #[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 #[serde(transparent)]
@@ -20,8 +22,30 @@ impl AssetId {
     }
 }
 
+#[derive(thiserror::Error, Debug)]
+#[non_exhaustive]
+pub enum VfsError {
+    #[error("invalid path {0:?}, expected /<root>/<path>")]
+    InvalidPath(String),
+
+    #[error("{path:?} not found, searched {searched:?}")]
+    NotFound {
+        path: String,
+        searched: Vec<PathBuf>,
+    },
+
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+struct ContentRoot {
+    path: PathBuf,
+    priority: i32,
+}
+
 pub struct Vfs {
-    roots: RwLock<AHashMap<String, PathBuf>>,
+    /// Directories of each root, highest priority first.
+    roots: RwLock<AHashMap<String, Vec<ContentRoot>>>,
 
     name_id_map: RwLock<AHashMap<String, AssetId>>,
     id_name_map: RwLock<AHashMap<AssetId, String>>,
@@ -38,28 +62,60 @@ impl Vfs {
     }
 
     pub fn add_root(&self, name: String, path: impl Into<PathBuf>) {
-        self.roots.write().unwrap().insert(name, path.into());
+        self.add_root_with_priority(name, path, 0);
+    }
+
+    /// Adds a directory to the root `name`. Files are looked up in the
+    /// directories of a root from the highest priority down, so e.g. a mod
+    /// directory can override some files of the base game. Directories
+    /// with the same priority are searched in the order they were added.
+    pub fn add_root_with_priority(&self, name: String, path: impl Into<PathBuf>, priority: i32) {
+        let mut roots = self.roots.write().unwrap();
+        let directories = roots.entry(name).or_default();
+
+        directories.push(ContentRoot {
+            path: path.into(),
+            priority,
+        });
+        directories.sort_by_key(|root| std::cmp::Reverse(root.priority));
     }
 
-    fn real_path(&self, path: &str) -> PathBuf {
-        let root_name = content_root_for_path(dbg!(path)).unwrap();
-        let root = self.roots.read().unwrap();
+    fn real_path(&self, path: &str) -> Result<PathBuf, VfsError> {
+        let invalid_path = || VfsError::InvalidPath(path.to_owned());
+
+        let root_name = content_root_for_path(path).ok_or_else(invalid_path)?;
 
         let relative_path = path
             .strip_prefix('/')
             .and_then(|path| path.strip_prefix(root_name))
             .and_then(|path| path.strip_prefix('/'))
-            .unwrap();
+            .ok_or_else(invalid_path)?;
+
+        let roots = self.roots.read().unwrap();
+        let mut searched = Vec::new();
 
-        root.get(root_name).unwrap().join(relative_path)
+        for root in roots.get(root_name).into_iter().flatten() {
+            let real_path = root.path.join(relative_path);
+
+            if real_path.is_file() {
+                return Ok(real_path);
+            }
+
+            searched.push(real_path);
+        }
+
+        Err(VfsError::NotFound {
+            path: path.to_owned(),
+            searched,
+        })
     }
 
-    pub fn load_binary_sync(&self, path: &str) -> Vec<u8> {
-        std::fs::read(self.real_path(path)).unwrap()
+    pub fn load_binary_sync(&self, path: &str) -> Result<Vec<u8>, VfsError> {
+        Ok(std::fs::read(self.real_path(path)?)?)
     }
 
-    pub fn load_string_sync(&self, path: &str) -> String {
-        std::fs::read_to_string(self.real_path(path)).unwrap()
+    pub fn load_string_sync(&self, path: &str) -> Result<String, VfsError> {
+        Ok(std::fs::read_to_string(self.real_path(path)?)?)
     }
 
     pub fn acquire_asset_id_for_path(&self, path: &str) -> AssetId {
@@ -84,7 +140,7 @@ impl Vfs {
         id
     }
 
-    pub fn load_by_id(&self, id: AssetId) -> Vec<u8> {
+    pub fn load_by_id(&self, id: AssetId) -> Result<Vec<u8>, VfsError> {
         let path = self.id_name_map.read().unwrap().get(&id).cloned().unwrap();
 
         self.load_binary_sync(&path)
@@ -94,3 +150,37 @@ impl Vfs {
 fn content_root_for_path(path: &str) -> Option<&str> {
     path.strip_prefix('/')?.split('/').next()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overlay_roots() {
+        let temp = std::env::temp_dir().join(format!("vl-vfs-{}", Uuid::new_v4()));
+        let base = temp.join("base");
+        let overlay = temp.join("overlay");
+
+        std::fs::create_dir_all(&base).unwrap();
+        std::fs::create_dir_all(&overlay).unwrap();
+        std::fs::write(base.join("a.txt"), "base a").unwrap();
+        std::fs::write(base.join("b.txt"), "base b").unwrap();
+        std::fs::write(overlay.join("a.txt"), "overlay a").unwrap();
+
+        let vfs = Vfs::new();
+        vfs.add_root("game".to_owned(), &base);
+        vfs.add_root_with_priority("game".to_owned(), &overlay, 1);
+
+        assert_eq!(vfs.load_string_sync("/game/a.txt").unwrap(), "overlay a");
+        assert_eq!(vfs.load_string_sync("/game/b.txt").unwrap(), "base b");
+
+        match vfs.load_binary_sync("/game/c.txt") {
+            Err(VfsError::NotFound { searched, .. }) => {
+                assert_eq!(searched, [overlay.join("c.txt"), base.join("c.txt")]);
+            }
+            other => panic!("expected not found, got {other:?}"),
+        }
+
+        std::fs::remove_dir_all(temp).unwrap();
+    }
+}
@@ -1,8 +1,11 @@
 use std::io::Cursor;
 
-use glam::{Vec2, Vec3};
+use ahash::AHashMap;
+use glam::{Mat3, Vec2, Vec3};
 use uuid::Uuid;
 
+use crate::math::Aabb;
+
 use wgpu;
 
 pub struct Vertex {
@@ -12,6 +15,23 @@ pub struct Vertex {
 }
 
 impl Vertex {
+    /// Bit pattern of all attributes, for deduplicating vertices.
+    fn key(&self) -> [u32; 8] {
+        let mut key = [0; 8];
+
+        for (bits, value) in key.iter_mut().zip(
+            self.position
+                .to_array()
+                .into_iter()
+                .chain(self.normal.to_array())
+                .chain(self.texcoord.to_array()),
+        ) {
+            *bits = value.to_bits();
+        }
+
+        key
+    }
+
     fn write(&self, data: &mut Vec<f32>) {
         data.extend_from_slice(&self.position.to_array());
         data.extend_from_slice(&self.normal.to_array());
@@ -48,6 +68,8 @@ pub struct Mesh {
     pub name: String,
     vertex_count: u32,
     data: Vec<f32>,
+    indices: Vec<u32>,
+    aabb: Option<Aabb>,
 }
 
 impl Mesh {
@@ -57,12 +79,33 @@ impl Mesh {
             name: String::new(),
             vertex_count: 0,
             data: Vec::new(),
+            indices: Vec::new(),
+            aabb: None,
         }
     }
 
-    pub fn add_vertex(&mut self, vertex: Vertex) {
-        self.vertex_count += 1;
+    /// Appends a vertex and returns its index. It's only drawn once it's
+    /// referenced by [`Mesh::add_triangle`].
+    pub fn add_vertex(&mut self, vertex: Vertex) -> u32 {
+        let point = Aabb::new(vertex.position, vertex.position);
+
+        self.aabb = Some(match self.aabb {
+            Some(aabb) => aabb.union(&point),
+            None => point,
+        });
+
         vertex.write(&mut self.data);
+        self.vertex_count += 1;
+        self.vertex_count - 1
+    }
+
+    pub fn add_triangle(&mut self, indices: [u32; 3]) {
+        self.indices.extend_from_slice(&indices);
+    }
+
+    /// Bounds of all vertices added so far, `None` for an empty mesh.
+    pub fn aabb(&self) -> Option<Aabb> {
+        self.aabb
     }
 
     pub fn vertex_count(&self) -> u32 {
@@ -72,6 +115,11 @@ impl Mesh {
     pub fn data(&self) -> &[f32] {
         &self.data
     }
+
+    /// Triangle list indexing into the vertices.
+    pub fn indices(&self) -> &[u32] {
+        &self.indices
+    }
 }
 
 pub struct Model {
@@ -96,36 +144,326 @@ impl Model {
     pub fn meshes(&self) -> impl Iterator<Item = &Mesh> {
         self.meshes.iter()
     }
+
+    /// Union of the bounds of all meshes, `None` if there are no vertices.
+    pub fn aabb(&self) -> Option<Aabb> {
+        self.meshes
+            .iter()
+            .filter_map(Mesh::aabb)
+            .reduce(|a, b| a.union(&b))
+    }
 }
 
-pub fn import_obj(data: &[u8]) -> Model {
-    let reader = Cursor::new(data);
-    let obj = obj::ObjData::load_buf(reader).unwrap();
+/// Which axis points up in the source file. The engine is Y-up.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum UpAxis {
+    #[default]
+    Y,
+    Z,
+}
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ImportOptions {
+    /// Generate normals for faces that don't have any, instead of leaving
+    /// them zeroed.
+    pub generate_normals: bool,
+    /// Generated normals of faces sharing a position are smoothed together
+    /// if the faces meet at an angle below this.
+    pub smoothing_angle_deg: f32,
+    /// Box-project texture coordinates for faces that don't have any,
+    /// instead of mapping them all to the center of the texture.
+    pub generate_uvs: bool,
+    pub up_axis: UpAxis,
+    pub scale: f32,
+    /// Mirror along Z, for files authored in a left-handed coordinate
+    /// system.
+    pub flip_handedness: bool,
+}
+
+impl Default for ImportOptions {
+    fn default() -> Self {
+        Self {
+            generate_normals: true,
+            smoothing_angle_deg: 60.0,
+            generate_uvs: false,
+            up_axis: UpAxis::Y,
+            scale: 1.0,
+            flip_handedness: false,
+        }
+    }
+}
+
+impl ImportOptions {
+    /// Transform from the source coordinate system into the engine's.
+    pub fn transform(&self) -> Mat3 {
+        let up = match self.up_axis {
+            UpAxis::Y => Mat3::IDENTITY,
+            UpAxis::Z => Mat3::from_rotation_x(-std::f32::consts::FRAC_PI_2),
+        };
+
+        let handedness = if self.flip_handedness {
+            Mat3::from_diagonal(Vec3::new(1.0, 1.0, -1.0))
+        } else {
+            Mat3::IDENTITY
+        };
+
+        up * handedness * Mat3::from_diagonal(Vec3::splat(self.scale))
+    }
+}
+
+/// How far along an import is, in meshes.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ImportProgress {
+    pub meshes_done: usize,
+    /// Zero until the file is parsed and the number of meshes is known.
+    pub mesh_count: usize,
+}
+
+pub fn import_obj(data: &[u8], options: &ImportOptions) -> Result<Model, obj::ObjError> {
     let mut model = Model::new();
+    import_obj_streaming(data, options, |mesh, _| model.add_mesh(mesh))?;
+    Ok(model)
+}
+
+/// Like [`import_obj`], but hands every mesh to `on_mesh` as soon as it's
+/// built instead of collecting them into a [`Model`]. The file itself is
+/// still parsed up front.
+pub fn import_obj_streaming(
+    data: &[u8],
+    options: &ImportOptions,
+    mut on_mesh: impl FnMut(Mesh, ImportProgress),
+) -> Result<(), obj::ObjError> {
+    let reader = Cursor::new(data);
+    let obj = obj::ObjData::load_buf(reader)?;
+
+    let transform = options.transform();
+    let normal_transform = transform.inverse().transpose();
+
+    // mirroring turns faces inside out unless their winding is flipped too
+    let flip_winding = transform.determinant() < 0.0;
 
-    let vertex = |indices: obj::IndexTuple| Vertex {
-        position: obj.position[indices.0].into(),
-        normal: indices.2.map(|n| obj.normal[n]).unwrap_or([0.0; 3]).into(),
-        texcoord: indices.1.map(|t| obj.texture[t]).unwrap_or([0.5; 2]).into(),
-    };
+    let positions: Vec<Vec3> = obj
+        .position
+        .iter()
+        .map(|&p| transform * Vec3::from(p))
+        .collect();
 
-    for group in obj.objects.iter().flat_map(|o| o.groups.iter()) {
+    let mesh_count = obj.objects.iter().map(|o| o.groups.len()).sum();
+
+    for (meshes_done, group) in obj.objects.iter().flat_map(|o| o.groups.iter()).enumerate() {
         let mut mesh = Mesh::new();
         mesh.name = group.name.clone();
 
-        for poly in &group.polys {
-            let base = poly.0[0];
+        // triangle fans, three corners per triangle
+        let corners: Vec<obj::IndexTuple> = group
+            .polys
+            .iter()
+            .flat_map(|poly| {
+                (1..poly.0.len().saturating_sub(1)).flat_map(move |i| match flip_winding {
+                    false => [poly.0[0], poly.0[i], poly.0[i + 1]],
+                    true => [poly.0[0], poly.0[i + 1], poly.0[i]],
+                })
+            })
+            .collect();
+
+        let generated_normals = (options.generate_normals
+            && corners.iter().any(|corner| corner.2.is_none()))
+        .then(|| {
+            let position_indices: Vec<usize> = corners.iter().map(|corner| corner.0).collect();
+            generate_normals(&positions, &position_indices, options.smoothing_angle_deg)
+        });
 
-            for i in 0..poly.0.len() - 2 {
-                mesh.add_vertex(vertex(base));
-                mesh.add_vertex(vertex(poly.0[i + 1]));
-                mesh.add_vertex(vertex(poly.0[i + 2]));
+        // corners sharing all attributes share a vertex
+        let mut vertex_indices: AHashMap<[u32; 8], u32> = AHashMap::new();
+        let mut triangle = [0; 3];
+
+        for (i, corner) in corners.iter().enumerate() {
+            let normal = match (corner.2, &generated_normals) {
+                (Some(n), _) => (normal_transform * Vec3::from(obj.normal[n])).normalize_or_zero(),
+                (None, Some(generated)) => generated[i],
+                (None, None) => Vec3::ZERO,
+            };
+
+            let position = positions[corner.0];
+
+            let texcoord = match corner.1 {
+                Some(t) => obj.texture[t].into(),
+                None if options.generate_uvs => {
+                    let triangle = &corners[i / 3 * 3..][..3];
+                    let [a, b, c] = [0, 1, 2].map(|j| positions[triangle[j].0]);
+                    box_project(position, (b - a).cross(c - a))
+                }
+                None => Vec2::splat(0.5),
+            };
+
+            let vertex = Vertex {
+                position,
+                normal,
+                texcoord,
+            };
+
+            triangle[i % 3] = *vertex_indices
+                .entry(vertex.key())
+                .or_insert_with(|| mesh.add_vertex(vertex));
+
+            if i % 3 == 2 {
+                mesh.add_triangle(triangle);
             }
         }
 
-        model.add_mesh(mesh);
+        on_mesh(
+            mesh,
+            ImportProgress {
+                meshes_done: meshes_done + 1,
+                mesh_count,
+            },
+        );
+    }
+
+    Ok(())
+}
+
+/// Projects `position` onto the axis plane the face is most aligned with,
+/// one UV unit per world unit.
+fn box_project(position: Vec3, face_normal: Vec3) -> Vec2 {
+    let n = face_normal.abs();
+
+    if n.x >= n.y && n.x >= n.z {
+        Vec2::new(position.z, position.y)
+    } else if n.y >= n.z {
+        Vec2::new(position.x, position.z)
+    } else {
+        Vec2::new(position.x, position.y)
     }
+}
+
+/// Per-corner normals of a triangle list, with `corners` indexing into
+/// `positions`. Faces sharing a position are weighted by their area.
+fn generate_normals(positions: &[Vec3], corners: &[usize], smoothing_angle_deg: f32) -> Vec<Vec3> {
+    let face_normals: Vec<Vec3> = corners
+        .chunks_exact(3)
+        .map(|triangle| {
+            let [a, b, c] = [triangle[0], triangle[1], triangle[2]].map(|i| positions[i]);
+            (b - a).cross(c - a)
+        })
+        .collect();
+
+    let mut faces_at_position: AHashMap<usize, Vec<usize>> = AHashMap::new();
+
+    for (corner, &position) in corners.iter().enumerate() {
+        faces_at_position
+            .entry(position)
+            .or_default()
+            .push(corner / 3);
+    }
+
+    let min_cos = smoothing_angle_deg.to_radians().cos();
+
+    corners
+        .iter()
+        .enumerate()
+        .map(|(corner, position)| {
+            let face_normal = face_normals[corner / 3].normalize_or_zero();
+
+            faces_at_position[position]
+                .iter()
+                .map(|&face| face_normals[face])
+                .filter(|normal| normal.normalize_or_zero().dot(face_normal) >= min_cos)
+                .sum::<Vec3>()
+                .normalize_or(face_normal)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    model
+    fn vertex(x: f32, y: f32, z: f32) -> Vertex {
+        Vertex {
+            position: Vec3::new(x, y, z),
+            normal: Vec3::Y,
+            texcoord: Vec2::ZERO,
+        }
+    }
+
+    #[test]
+    fn aabb_covers_vertices() {
+        let mut a = Mesh::new();
+        a.add_vertex(vertex(1.0, 2.0, 3.0));
+        a.add_vertex(vertex(-1.0, 0.0, 5.0));
+
+        let mut b = Mesh::new();
+        b.add_vertex(vertex(0.0, -4.0, 0.0));
+
+        assert_eq!(
+            a.aabb(),
+            Some(Aabb::new(
+                Vec3::new(-1.0, 0.0, 3.0),
+                Vec3::new(1.0, 2.0, 5.0)
+            ))
+        );
+
+        let mut model = Model::new();
+        assert_eq!(model.aabb(), None);
+
+        model.add_mesh(a);
+        model.add_mesh(b);
+        model.add_mesh(Mesh::new());
+
+        assert_eq!(
+            model.aabb(),
+            Some(Aabb::new(
+                Vec3::new(-1.0, -4.0, 0.0),
+                Vec3::new(1.0, 2.0, 5.0)
+            ))
+        );
+    }
+
+    #[test]
+    fn generated_normals_respect_smoothing_angle() {
+        // two triangles folded 90 degrees along the edge 0-1
+        let positions = [
+            Vec3::new(0.0, 0.0, 0.0),
+            Vec3::new(1.0, 0.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            Vec3::new(0.0, 0.0, -1.0),
+        ];
+        let corners = [0, 1, 2, 0, 1, 3];
+
+        let sharp = generate_normals(&positions, &corners, 60.0);
+        assert_eq!(sharp[0], Vec3::Z);
+        assert_eq!(sharp[3], Vec3::Y);
+
+        let smooth = generate_normals(&positions, &corners, 100.0);
+        assert!(smooth[0].abs_diff_eq(Vec3::new(0.0, 1.0, 1.0).normalize(), 1e-6));
+        assert_eq!(smooth[2], Vec3::Z);
+    }
+
+    #[test]
+    fn box_projection_follows_dominant_axis() {
+        let position = Vec3::new(1.0, 2.0, 3.0);
+
+        assert_eq!(
+            box_project(position, Vec3::new(-0.9, 0.1, 0.3)),
+            Vec2::new(3.0, 2.0)
+        );
+        assert_eq!(box_project(position, Vec3::Y), Vec2::new(1.0, 3.0));
+        assert_eq!(box_project(position, Vec3::NEG_Z), Vec2::new(1.0, 2.0));
+    }
+
+    #[test]
+    fn z_up_import_transform() {
+        let options = ImportOptions {
+            up_axis: UpAxis::Z,
+            scale: 2.0,
+            ..Default::default()
+        };
+
+        let transform = options.transform();
+
+        assert!((transform * Vec3::Z).abs_diff_eq(Vec3::new(0.0, 2.0, 0.0), 1e-6));
+        assert!((transform * Vec3::Y).abs_diff_eq(Vec3::new(0.0, 0.0, -2.0), 1e-6));
+        assert_eq!(ImportOptions::default().transform(), Mat3::IDENTITY);
+    }
 }
@@ -0,0 +1,3 @@
+pub fn import_image(data: &[u8]) -> Result<image::RgbaImage, image::ImageError> {
+    Ok(image::load_from_memory(data)?.into_rgba8())
+}
@@ -0,0 +1,150 @@
+/// Linear RGBA color with straight (not premultiplied) alpha.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Color {
+    pub r: f32,
+    pub g: f32,
+    pub b: f32,
+    pub a: f32,
+}
+
+impl Color {
+    pub const BLACK: Color = Color::new(0.0, 0.0, 0.0, 1.0);
+    pub const WHITE: Color = Color::new(1.0, 1.0, 1.0, 1.0);
+    pub const TRANSPARENT: Color = Color::new(0.0, 0.0, 0.0, 0.0);
+
+    pub const fn new(r: f32, g: f32, b: f32, a: f32) -> Self {
+        Self { r, g, b, a }
+    }
+
+    /// Unpacks a `0xRRGGBBAA` color, e.g. `0x102030FF`, red in the most
+    /// significant byte. Components are taken as linear, without any sRGB
+    /// decoding.
+    pub fn from_rgba_u32(rgba: u32) -> Self {
+        let channel = |shift: u32| ((rgba >> shift) & 0xFF) as f32 / 255.0;
+
+        Self {
+            r: channel(24),
+            g: channel(16),
+            b: channel(8),
+            a: channel(0),
+        }
+    }
+
+    /// Packs the color into `0xRRGGBBAA`. Components are clamped to `[0, 1]`.
+    pub fn to_rgba_u32(self) -> u32 {
+        let channel =
+            |value: f32, shift: u32| ((value.clamp(0.0, 1.0) * 255.0).round() as u32) << shift;
+
+        channel(self.r, 24) | channel(self.g, 16) | channel(self.b, 8) | channel(self.a, 0)
+    }
+
+    pub fn from_array(rgba: [f32; 4]) -> Self {
+        let [r, g, b, a] = rgba;
+        Self { r, g, b, a }
+    }
+
+    pub fn to_array(self) -> [f32; 4] {
+        [self.r, self.g, self.b, self.a]
+    }
+
+    /// Decodes 8-bit sRGB components, as used by most color pickers and
+    /// image files. Alpha is linear in sRGB too.
+    pub fn from_srgba_u8(srgba: [u8; 4]) -> Self {
+        let [r, g, b, a] = srgba.map(|c| c as f32 / 255.0);
+
+        Self {
+            r: srgb_to_linear(r),
+            g: srgb_to_linear(g),
+            b: srgb_to_linear(b),
+            a,
+        }
+    }
+
+    /// Encodes the color as 8-bit sRGB. Components are clamped to `[0, 1]`.
+    pub fn to_srgba_u8(self) -> [u8; 4] {
+        let encode = |c: f32| (c.clamp(0.0, 1.0) * 255.0).round() as u8;
+
+        [
+            encode(linear_to_srgb(self.r)),
+            encode(linear_to_srgb(self.g)),
+            encode(linear_to_srgb(self.b)),
+            encode(self.a),
+        ]
+    }
+}
+
+impl From<[f32; 4]> for Color {
+    fn from(value: [f32; 4]) -> Self {
+        Self::from_array(value)
+    }
+}
+
+impl From<Color> for [f32; 4] {
+    fn from(value: Color) -> Self {
+        value.to_array()
+    }
+}
+
+impl From<egui::Color32> for Color {
+    fn from(value: egui::Color32) -> Self {
+        Self::from_srgba_u8(value.to_srgba_unmultiplied())
+    }
+}
+
+impl From<Color> for egui::Color32 {
+    fn from(value: Color) -> Self {
+        let [r, g, b, a] = value.to_srgba_u8();
+        egui::Color32::from_rgba_unmultiplied(r, g, b, a)
+    }
+}
+
+pub fn srgb_to_linear(c: f32) -> f32 {
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+pub fn linear_to_srgb(c: f32) -> f32 {
+    if c <= 0.0031308 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rgba_u32_layout() {
+        let color = Color::from_rgba_u32(0xFF000080);
+
+        assert_eq!(color.r, 1.0);
+        assert_eq!(color.g, 0.0);
+        assert_eq!(color.b, 0.0);
+        assert_eq!(color.a, 128.0 / 255.0);
+    }
+
+    #[test]
+    fn rgba_u32_roundtrip() {
+        for value in 0..=255u32 {
+            let packed = value << 24 | (255 - value) << 16 | value << 8 | (value ^ 0x5A);
+
+            assert_eq!(Color::from_rgba_u32(packed).to_rgba_u32(), packed);
+        }
+    }
+
+    #[test]
+    fn srgb_roundtrip() {
+        assert!((srgb_to_linear(0.5) - 0.21404).abs() < 1e-5);
+
+        for value in 0..=255u8 {
+            let srgba = [value, 255 - value, value / 2, value];
+
+            assert_eq!(Color::from_srgba_u8(srgba).to_srgba_u8(), srgba);
+        }
+    }
+}
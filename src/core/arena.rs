@@ -84,6 +84,26 @@ impl<T> Arena<T> {
         return cell.item.as_mut();
     }
 
+    /// Whether `handle` refers to a live item. Unlike [`Arena::get`], this
+    /// doesn't panic on stale or out of bounds handles.
+    pub fn contains(&self, handle: ArenaHandle<T>) -> bool {
+        self.cells
+            .get(handle.index as usize)
+            .is_some_and(|cell| cell.generation == handle.generation && cell.item.is_some())
+    }
+
+    /// Returns the handle of the live item stored at `index`, if any.
+    pub fn handle_at(&self, index: u32) -> Option<ArenaHandle<T>> {
+        let cell = self.cells.get(index as usize)?;
+        cell.item.as_ref()?;
+
+        Some(ArenaHandle {
+            index,
+            generation: cell.generation,
+            _pd: PhantomData,
+        })
+    }
+
     pub fn len(&self) -> usize {
         self.len
     }
@@ -151,6 +171,10 @@ impl<T> ArenaHandle<T> {
         generation: 0,
         _pd: PhantomData,
     };
+
+    pub fn index(&self) -> u32 {
+        self.index
+    }
 }
 
 impl<T> PartialEq for ArenaHandle<T> {
@@ -161,6 +185,22 @@ impl<T> PartialEq for ArenaHandle<T> {
 
 impl<T> Eq for ArenaHandle<T> {}
 
+impl<T> std::hash::Hash for ArenaHandle<T> {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.index.hash(state);
+        self.generation.hash(state);
+    }
+}
+
+impl<T> std::fmt::Debug for ArenaHandle<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ArenaHandle")
+            .field("index", &self.index)
+            .field("generation", &self.generation)
+            .finish()
+    }
+}
+
 impl<T> Clone for ArenaHandle<T> {
     fn clone(&self) -> Self {
         *self
@@ -223,6 +263,22 @@ mod tests {
         assert_eq!(arena.get(a1), Some(&"b1"));
     }
 
+    #[test]
+    fn handle_at_index() {
+        let mut arena = Arena::new();
+
+        let a1 = arena.insert("a1");
+        let b1 = arena.insert("b1");
+        arena.remove(a1);
+
+        assert_eq!(arena.handle_at(a1.index()), None);
+        assert_eq!(arena.handle_at(b1.index()), Some(b1));
+        assert_eq!(arena.handle_at(2), None);
+
+        let a2 = arena.insert("a2");
+        assert_eq!(arena.handle_at(a1.index()), Some(a2));
+    }
+
     #[test]
     fn free_list_works() {
         let mut arena = Arena::new();
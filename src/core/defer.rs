@@ -29,8 +29,9 @@ pub struct Defer<'a> {
 
 impl<'a> SystemParam for Defer<'a> {
     type Item<'w> = Defer<'w>;
+    type State = ();
 
-    fn get(reg: &Registry) -> Self::Item<'_> {
+    fn get<'w>(reg: &'w Registry, _: &mut ()) -> Self::Item<'w> {
         Defer {
             queue: reg.defer_queue.borrow_mut(),
         }
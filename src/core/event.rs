@@ -1,25 +1,148 @@
 use std::any::Any;
 use std::cell::{Ref, RefMut};
+use std::collections::VecDeque;
+use std::ops::Range;
 
-use crate::core::{Defer, Registry, SystemParam};
+use tracing::warn;
+
+use crate::core::{Registry, SystemParam};
 
 pub trait AnyEventQueue {
     fn as_any(&self) -> &dyn Any;
     fn as_any_mut(&mut self) -> &mut dyn Any;
-    fn clear(&mut self);
+    fn update(&mut self);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    DropOldest,
+    DropNewest,
+    Panic,
 }
 
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct EventCursor {
+    next: u64,
+}
+
+/// Events stay readable for the rest of the step they're emitted in and the
+/// whole next one.
 pub struct EventQueue<E> {
-    events: Vec<E>,
+    previous: VecDeque<E>,
+    current: VecDeque<E>,
+    /// Id of the next event, readable events have the ids right before it.
+    emitted: u64,
+    capacity: Option<usize>,
+    policy: OverflowPolicy,
+    dropped: u64,
 }
 
 impl<E> EventQueue<E> {
     pub(super) fn new() -> Self {
-        Self { events: Vec::new() }
+        Self {
+            previous: VecDeque::new(),
+            current: VecDeque::new(),
+            emitted: 0,
+            capacity: None,
+            policy: OverflowPolicy::DropOldest,
+            dropped: 0,
+        }
+    }
+
+    pub(super) fn with_capacity(capacity: usize, policy: OverflowPolicy) -> Self {
+        assert!(capacity > 0, "event queue capacity must be positive");
+
+        Self {
+            capacity: Some(capacity),
+            policy,
+            ..Self::new()
+        }
     }
 
     pub fn emit(&mut self, event: E) {
-        self.events.push(event);
+        let full = self.capacity.is_some_and(|capacity| self.len() >= capacity);
+
+        if full {
+            match self.policy {
+                OverflowPolicy::DropOldest => {
+                    if self.previous.pop_front().is_none() {
+                        self.current.pop_front();
+                    }
+
+                    self.count_dropped();
+                }
+                OverflowPolicy::DropNewest => {
+                    self.count_dropped();
+                    return;
+                }
+                OverflowPolicy::Panic => panic!(
+                    "{} event queue overflowed its capacity of {}",
+                    std::any::type_name::<E>(),
+                    self.capacity.unwrap()
+                ),
+            }
+        }
+
+        self.current.push_back(event);
+        self.emitted += 1;
+    }
+
+    fn count_dropped(&mut self) {
+        if self.dropped == 0 {
+            warn!(
+                event = std::any::type_name::<E>(),
+                capacity = self.capacity,
+                "event queue is full, dropping events"
+            );
+        }
+
+        self.dropped += 1;
+    }
+
+    pub fn len(&self) -> usize {
+        self.previous.len() + self.current.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+
+    pub fn read(&self, cursor: &mut EventCursor) -> impl Iterator<Item = &E> {
+        let unseen = self.advance(cursor);
+        self.iter_ids(unseen)
+    }
+
+    fn advance(&self, cursor: &mut EventCursor) -> Range<u64> {
+        let unseen = cursor.next.max(self.first_id())..self.emitted;
+        cursor.next = self.emitted;
+
+        unseen
+    }
+
+    fn first_id(&self) -> u64 {
+        self.emitted - self.len() as u64
+    }
+
+    fn iter_ids(&self, ids: Range<u64>) -> impl Iterator<Item = &E> {
+        // some of `ids` may have been dropped since
+        let start = ids.start.max(self.first_id());
+        let count = ids.end.saturating_sub(start);
+
+        self.iter()
+            .skip((start - self.first_id()) as usize)
+            .take(count as usize)
+    }
+
+    fn iter(&self) -> impl Iterator<Item = &E> {
+        self.previous.iter().chain(self.current.iter())
+    }
+
+    pub fn drain(&mut self) -> impl Iterator<Item = E> + '_ {
+        self.previous.drain(..).chain(self.current.drain(..))
     }
 }
 
@@ -32,59 +155,174 @@ impl<E: 'static> AnyEventQueue for EventQueue<E> {
         self
     }
 
-    fn clear(&mut self) {
-        self.events.clear();
+    fn update(&mut self) {
+        std::mem::swap(&mut self.previous, &mut self.current);
+        self.current.clear();
     }
 }
 
-pub fn clear_events(mut defer: Defer) {
-    defer.defer(|reg| {
-        for queue in reg.event_queues.values() {
-            queue.borrow_mut().clear();
-        }
-    });
-}
-
 pub struct Events<'a, E> {
     value: Ref<'a, EventQueue<E>>,
+    unseen: Range<u64>,
 }
 
 impl<'a, E: 'static> SystemParam for Events<'a, E> {
     type Item<'w> = Events<'w, E>;
+    type State = EventCursor;
 
-    fn get(reg: &Registry) -> Self::Item<'_> {
-        Events {
-            value: reg.event_queue::<E>(),
-        }
+    fn get<'w>(reg: &'w Registry, cursor: &mut EventCursor) -> Self::Item<'w> {
+        let value = reg.event_queue::<E>();
+        let unseen = value.advance(cursor);
+
+        Events { value, unseen }
     }
 }
 
 impl<E> Events<'_, E> {
     pub fn iter(&self) -> impl Iterator<Item = &E> {
-        self.value.events.iter()
+        self.value.iter_ids(self.unseen.clone())
+    }
+
+    pub fn dropped(&self) -> u64 {
+        self.value.dropped()
     }
 }
 
 pub struct EventsMut<'a, E> {
     value: RefMut<'a, EventQueue<E>>,
+    unseen: Range<u64>,
 }
 
 impl<'a, E: 'static> SystemParam for EventsMut<'a, E> {
     type Item<'w> = EventsMut<'w, E>;
+    type State = EventCursor;
 
-    fn get(reg: &Registry) -> Self::Item<'_> {
-        EventsMut {
-            value: reg.event_queue_mut::<E>(),
-        }
+    fn get<'w>(reg: &'w Registry, cursor: &mut EventCursor) -> Self::Item<'w> {
+        let value = reg.event_queue_mut::<E>();
+        let unseen = value.advance(cursor);
+
+        EventsMut { value, unseen }
     }
 }
 
 impl<E> EventsMut<'_, E> {
+    /// Doesn't include events emitted during this run.
     pub fn iter(&self) -> impl Iterator<Item = &E> {
-        self.value.events.iter()
+        self.value.iter_ids(self.unseen.clone())
     }
 
     pub fn emit(&mut self, event: E) {
         self.value.emit(event)
     }
+
+    pub fn dropped(&self) -> u64 {
+        self.value.dropped()
+    }
+
+    pub fn drain(&mut self) -> impl Iterator<Item = E> + '_ {
+        self.value.drain()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{ResMut, Schedule, Stage};
+
+    #[test]
+    fn events_live_for_two_steps() {
+        let mut reg = Registry::new();
+        reg.register_event::<u32>();
+
+        reg.event_queue_mut::<u32>().emit(1);
+        assert_eq!(reg.event_queue::<u32>().iter().collect::<Vec<_>>(), [&1]);
+
+        reg.next_step();
+        reg.event_queue_mut::<u32>().emit(2);
+        assert_eq!(
+            reg.event_queue::<u32>().iter().collect::<Vec<_>>(),
+            [&1, &2]
+        );
+
+        reg.next_step();
+        assert_eq!(reg.event_queue::<u32>().iter().collect::<Vec<_>>(), [&2]);
+
+        reg.next_step();
+        assert_eq!(reg.event_queue::<u32>().iter().count(), 0);
+    }
+
+    #[test]
+    fn system_reads_event_once() {
+        fn read(events: Events<u32>, mut seen: ResMut<Vec<u32>>) {
+            seen.extend(events.iter());
+        }
+
+        let mut reg = Registry::new();
+        reg.register_event::<u32>();
+        reg.insert(Vec::<u32>::new());
+
+        let mut schedule = Schedule::new();
+        schedule.add(read);
+
+        reg.event_queue_mut::<u32>().emit(1);
+        schedule.execute(Stage::EachStep, &mut reg);
+
+        reg.next_step();
+        reg.event_queue_mut::<u32>().emit(2);
+        schedule.execute(Stage::EachStep, &mut reg);
+
+        reg.next_step();
+        schedule.execute(Stage::EachStep, &mut reg);
+
+        assert_eq!(*reg.res::<Vec<u32>>(), [1, 2]);
+    }
+
+    #[test]
+    fn cursor_skips_dropped_events() {
+        let mut queue = EventQueue::with_capacity(2, OverflowPolicy::DropOldest);
+        let mut cursor = EventCursor::default();
+
+        queue.emit(1);
+        assert_eq!(queue.read(&mut cursor).collect::<Vec<_>>(), [&1]);
+
+        for event in 2..=4 {
+            queue.emit(event);
+        }
+
+        assert_eq!(queue.read(&mut cursor).collect::<Vec<_>>(), [&3, &4]);
+        assert_eq!(queue.read(&mut cursor).count(), 0);
+    }
+
+    #[test]
+    fn drain_consumes_once() {
+        let mut reg = Registry::new();
+        reg.register_event::<u32>();
+
+        reg.event_queue_mut::<u32>().emit(1);
+        reg.next_step();
+        reg.event_queue_mut::<u32>().emit(2);
+
+        let drained: Vec<_> = reg.event_queue_mut::<u32>().drain().collect();
+        assert_eq!(drained, [1, 2]);
+        assert_eq!(reg.event_queue::<u32>().iter().count(), 0);
+    }
+
+    #[test]
+    fn full_queue_drops_events() {
+        let mut oldest = EventQueue::with_capacity(2, OverflowPolicy::DropOldest);
+        let mut newest = EventQueue::with_capacity(2, OverflowPolicy::DropNewest);
+
+        for event in 1..=3 {
+            oldest.emit(event);
+            newest.emit(event);
+        }
+
+        oldest.update();
+        oldest.emit(4);
+
+        assert_eq!(oldest.iter().collect::<Vec<_>>(), [&3, &4]);
+        assert_eq!(oldest.dropped(), 2);
+        assert_eq!(newest.iter().collect::<Vec<_>>(), [&1, &2]);
+        assert_eq!(newest.dropped(), 1);
+    }
 }
@@ -1,4 +1,6 @@
+use std::any::Any;
 use std::marker::PhantomData;
+use std::time::{Duration, Instant};
 
 use ahash::AHashMap;
 
@@ -9,6 +11,9 @@ pub struct SystemFn<F, FnParams> {
 
     step: Step,
 
+    /// States of the system's parameters, created on the first run.
+    state: Option<Box<dyn Any>>,
+
     // needed to constrain input types for System impl
     _pd: PhantomData<FnParams>,
 }
@@ -18,12 +23,17 @@ pub struct SystemFn<F, FnParams> {
 pub trait SystemParam {
     type Item<'w>;
 
-    fn get(reg: &Registry) -> Self::Item<'_>;
+    /// Kept by the system between runs, e.g. how far it has read an event
+    /// queue.
+    type State: Default + 'static;
+
+    fn get<'w>(reg: &'w Registry, state: &mut Self::State) -> Self::Item<'w>;
 }
 
 type SystemParamItem<'w, T> = <T as SystemParam>::Item<'w>;
 
 pub trait System {
+    fn name(&self) -> &'static str;
     fn run(&mut self, reg: &Registry);
     fn step(&self) -> Step;
     fn set_step(&mut self, step: Step);
@@ -38,6 +48,16 @@ pub trait IntoSystem<I, S> {
 pub enum Stage {
     Init,
     EachStep,
+    /// Runs once before the event loop exits, while all resources are still
+    /// alive.
+    Shutdown,
+}
+
+/// Time a system took to run, including applying its deferred commands.
+#[derive(Debug, Clone)]
+pub struct SystemTiming {
+    pub name: &'static str,
+    pub duration: Duration,
 }
 
 pub struct Schedule {
@@ -59,17 +79,50 @@ impl Schedule {
         self.plan_at(Stage::EachStep, s)
     }
 
+    pub fn add_shutdown<I, S: System + 'static>(&mut self, s: impl IntoSystem<I, S>) {
+        self.plan_at(Stage::Shutdown, s)
+    }
+
     pub fn plan_at<I, S: System + 'static>(&mut self, stage: Stage, s: impl IntoSystem<I, S>) {
         let systems = self.systems.entry(stage).or_default();
         systems.push(Box::new(s.into_system()));
     }
 
     pub fn execute(&mut self, stage: Stage, reg: &mut Registry) {
+        self.execute_inner(stage, reg, None);
+    }
+
+    /// Same as [`Schedule::execute`], but also appends how long each system
+    /// took to `timings`.
+    pub fn execute_profiled(
+        &mut self,
+        stage: Stage,
+        reg: &mut Registry,
+        timings: &mut Vec<SystemTiming>,
+    ) {
+        self.execute_inner(stage, reg, Some(timings));
+    }
+
+    fn execute_inner(
+        &mut self,
+        stage: Stage,
+        reg: &mut Registry,
+        mut timings: Option<&mut Vec<SystemTiming>>,
+    ) {
         for system in self.systems.entry(stage).or_default() {
+            let start = Instant::now();
+
             system.run(reg);
             let mut defer_queue = reg.defer_queue.replace(Default::default());
             defer_queue.apply(reg);
             system.set_step(reg.step);
+
+            if let Some(timings) = timings.as_deref_mut() {
+                timings.push(SystemTiming {
+                    name: system.name(),
+                    duration: start.elapsed(),
+                });
+            }
         }
     }
 }
@@ -85,9 +138,21 @@ macro_rules! impl_system_for_systemfn {
             for<'a, 'b> &'a mut Func:
                 FnMut($($ts),*) + FnMut($(SystemParamItem<'b, $ts>),*),
         {
+            fn name(&self) -> &'static str {
+                std::any::type_name::<Func>()
+            }
+
             fn run(&mut self, reg: &Registry) {
+                let state = self
+                    .state
+                    .get_or_insert_with(|| Box::new(($($ts::State::default(),)*)))
+                    .downcast_mut::<($($ts::State,)*)>()
+                    .unwrap();
+
+                let ($($ts,)*) = state;
+
                 $(
-                    let $ts = $ts::get(reg);
+                    let $ts = $ts::get(reg, $ts);
                 )*
 
                 fn call_inner<$($ts),*>(mut f: impl FnMut($($ts),*), $($ts:$ts),*) {
@@ -121,6 +186,7 @@ macro_rules! impl_into_system_for_fn {
                 SystemFn {
                     func: self,
                     step: Step::new(0),
+                    state: None,
                     _pd: PhantomData,
                 }
             }
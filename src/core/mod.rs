@@ -39,12 +39,52 @@ impl Registry {
         self.resources.insert(id, Box::new(RefCell::new(r)));
     }
 
+    pub fn contains<R: 'static>(&self) -> bool {
+        self.resources.contains_key(&TypeId::of::<R>())
+    }
+
+    pub fn remove<R: 'static>(&mut self) -> Option<R> {
+        let id = TypeId::of::<R>();
+        let resource = self.resources.remove(&id)?;
+
+        // SAFETY: resources are keyed by their TypeId, so this one is an R
+        let resource = unsafe { Box::from_raw(Box::into_raw(resource) as *mut RefCell<R>) };
+
+        Some(resource.into_inner())
+    }
+
+    /// Returns the resource, inserting the result of `f` first if it's
+    /// missing.
+    pub fn get_or_insert_with<R: 'static>(&mut self, f: impl FnOnce() -> R) -> RefMut<R> {
+        if !self.contains::<R>() {
+            self.insert(f());
+        }
+
+        self.res_mut()
+    }
+
     pub fn register_event<E: 'static>(&mut self) {
         let id = TypeId::of::<E>();
         self.event_queues
             .insert(id, Box::new(RefCell::new(EventQueue::<E>::new())));
     }
 
+    /// Like [`Registry::register_event`], but keeps at most `capacity`
+    /// events around, dealing with more according to `policy`.
+    pub fn register_event_with_capacity<E: 'static>(
+        &mut self,
+        capacity: usize,
+        policy: OverflowPolicy,
+    ) {
+        let id = TypeId::of::<E>();
+        self.event_queues.insert(
+            id,
+            Box::new(RefCell::new(EventQueue::<E>::with_capacity(
+                capacity, policy,
+            ))),
+        );
+    }
+
     #[track_caller]
     pub fn event_queue<E: 'static>(&self) -> Ref<EventQueue<E>> {
         let id = TypeId::of::<E>();
@@ -100,6 +140,10 @@ impl Registry {
     }
 
     pub fn next_step(&mut self) {
+        for queue in self.event_queues.values() {
+            queue.borrow_mut().update();
+        }
+
         self.step.increment();
     }
 }
@@ -131,3 +175,28 @@ macro_rules! expand_macro_staircase {
 }
 
 pub(crate) use expand_macro_staircase;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn remove_resource() {
+        let mut reg = Registry::new();
+        reg.insert(String::from("hello"));
+
+        assert_eq!(reg.remove::<String>().as_deref(), Some("hello"));
+        assert_eq!(reg.remove::<String>(), None);
+        assert!(!reg.contains::<String>());
+    }
+
+    #[test]
+    fn get_or_insert_with_inserts_once() {
+        let mut reg = Registry::new();
+
+        *reg.get_or_insert_with(|| 1u32) += 1;
+        *reg.get_or_insert_with(|| 10u32) += 1;
+
+        assert_eq!(*reg.res::<u32>(), 3);
+    }
+}
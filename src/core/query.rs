@@ -10,8 +10,9 @@ pub struct Res<'a, T: 'static> {
 
 impl<'a, T> SystemParam for Res<'a, T> {
     type Item<'w> = Res<'w, T>;
+    type State = ();
 
-    fn get(reg: &Registry) -> Self::Item<'_> {
+    fn get<'w>(reg: &'w Registry, _: &mut ()) -> Self::Item<'w> {
         Res {
             value: reg.res::<T>(),
         }
@@ -32,8 +33,9 @@ pub struct ResMut<'a, T: 'static> {
 
 impl<'a, T> SystemParam for ResMut<'a, T> {
     type Item<'w> = ResMut<'w, T>;
+    type State = ();
 
-    fn get(reg: &Registry) -> Self::Item<'_> {
+    fn get<'w>(reg: &'w Registry, _: &mut ()) -> Self::Item<'w> {
         ResMut {
             value: reg.res_mut::<T>(),
         }
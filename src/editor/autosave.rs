@@ -0,0 +1,122 @@
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use tracing::{error, info};
+
+use crate::editor::{Editor, EditorLayout};
+use crate::scene::{Scene, SceneData, SceneGraph, SceneHandle};
+
+/// Periodically writes all scenes and the editor layout to a recovery file,
+/// separate from any file the user saves to. The file is removed on a clean exit, so finding
+/// one at startup means the previous session ended unexpectedly.
+pub struct AutoSave {
+    path: PathBuf,
+    last_save: Instant,
+    recovered: Option<Recovery>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Recovery {
+    scenes: Vec<SceneData>,
+    layout: EditorLayout,
+}
+
+impl AutoSave {
+    pub fn new() -> Self {
+        let path = recovery_path();
+
+        let recovered = std::fs::read(&path)
+            .ok()
+            .and_then(|data| serde_json::from_slice(&data).ok());
+
+        Self {
+            path,
+            last_save: Instant::now(),
+            recovered,
+        }
+    }
+
+    /// Whether scenes from a previous session are waiting to be restored or
+    /// discarded. Auto-saving is paused until then, so the recovery file
+    /// isn't overwritten.
+    pub fn has_recovery(&self) -> bool {
+        self.recovered.is_some()
+    }
+
+    /// Replaces scenes with the recovered ones in creation order, adding
+    /// any extra ones, and restores the editor layout. Scenes that fail
+    /// validation are skipped.
+    pub fn restore(&mut self, sg: &mut SceneGraph, editor: &mut Editor) {
+        let Some(recovery) = self.recovered.take() else {
+            return;
+        };
+
+        let existing: Vec<SceneHandle> = sg.scenes().map(|(handle, _)| handle).collect();
+
+        for (index, data) in recovery.scenes.into_iter().enumerate() {
+            if let Err(err) = data.validate() {
+                error!(index, %err, "skipping invalid recovered scene");
+                continue;
+            }
+
+            let scene = Scene::from_data(data);
+
+            match existing.get(index) {
+                Some(&handle) => *sg.scene_mut(handle).unwrap() = scene,
+                None => {
+                    sg.add_scene(scene);
+                }
+            }
+        }
+
+        // the old selection points into the replaced node arenas
+        editor.apply_layout(&recovery.layout, sg);
+    }
+
+    pub fn discard_recovery(&mut self) {
+        self.recovered = None;
+        let _ = std::fs::remove_file(&self.path);
+    }
+
+    /// Called on a clean exit. Removes this session's auto-save, unless a
+    /// recovery from an earlier session is still waiting for a decision.
+    pub fn finish(&mut self) {
+        if !self.has_recovery() {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+
+    pub(super) fn save_if_due(&mut self, interval: Duration, sg: &SceneGraph, editor: &Editor) {
+        if interval.is_zero() || self.has_recovery() || self.last_save.elapsed() < interval {
+            return;
+        }
+
+        self.last_save = Instant::now();
+
+        // serializing is cheap compared to the disk write, which happens off
+        // the main thread
+        let recovery = Recovery {
+            scenes: sg.scenes().map(|(_, scene)| scene.to_data()).collect(),
+            layout: editor.layout(sg),
+        };
+        let data = serde_json::to_vec(&recovery).unwrap();
+        let path = self.path.clone();
+
+        rayon::spawn(move || match write_atomically(&path, &data) {
+            Ok(()) => info!(?path, "auto-saved scenes"),
+            Err(err) => error!(?path, %err, "auto-save failed"),
+        });
+    }
+}
+
+fn write_atomically(path: &Path, data: &[u8]) -> std::io::Result<()> {
+    let temporary_path = path.with_extension("tmp");
+
+    std::fs::write(&temporary_path, data)?;
+    std::fs::rename(temporary_path, path)
+}
+
+fn recovery_path() -> PathBuf {
+    PathBuf::from("videoland.recovery.json")
+}
@@ -0,0 +1,111 @@
+use glam::Vec3;
+
+use crate::color::Color;
+use crate::render::DebugDraw;
+
+/// Upper bound on grid lines per axis, so tiny spacings don't flood the
+/// debug line buffer.
+const MAX_GRID_LINES: u32 = 256;
+
+#[derive(Debug, Clone, Copy, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct GridSettings {
+    pub visible: bool,
+    /// Distance between adjacent lines, also used as the translation snap
+    /// increment.
+    pub spacing: f32,
+    /// Every n-th line is drawn as a major line.
+    pub major_interval: u32,
+    /// Half size of the grid around the origin.
+    pub extent: f32,
+}
+
+impl Default for GridSettings {
+    fn default() -> Self {
+        Self {
+            visible: true,
+            spacing: 1.0,
+            major_interval: 10,
+            extent: 50.0,
+        }
+    }
+}
+
+impl GridSettings {
+    /// Rounds a position on the XZ plane to the nearest grid intersection.
+    pub fn snap(&self, position: Vec3) -> Vec3 {
+        if self.spacing <= 0.0 {
+            return position;
+        }
+
+        let snapped = (position / self.spacing).round() * self.spacing;
+
+        Vec3::new(snapped.x, position.y, snapped.z)
+    }
+
+    pub fn draw(&self, debug_draw: &mut DebugDraw) {
+        if !self.visible || self.spacing <= 0.0 || self.extent <= 0.0 {
+            return;
+        }
+
+        let half_count = ((self.extent / self.spacing) as u32).min(MAX_GRID_LINES / 2) as i32;
+        let extent = half_count as f32 * self.spacing;
+
+        let minor = Color::new(0.5, 0.5, 0.5, 0.25);
+        let major = Color::new(0.7, 0.7, 0.7, 0.6);
+
+        debug_draw.with_depth_test(|debug_draw| {
+            for i in -half_count..=half_count {
+                let color = if self.major_interval > 0 && i % self.major_interval as i32 == 0 {
+                    major
+                } else {
+                    minor
+                };
+
+                let offset = i as f32 * self.spacing;
+
+                debug_draw.line(
+                    Vec3::new(offset, 0.0, -extent),
+                    Vec3::new(offset, 0.0, extent),
+                    color,
+                );
+                debug_draw.line(
+                    Vec3::new(-extent, 0.0, offset),
+                    Vec3::new(extent, 0.0, offset),
+                    color,
+                );
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snap_rounds_to_grid_on_xz_plane() {
+        let grid = GridSettings {
+            spacing: 0.5,
+            ..Default::default()
+        };
+
+        let snapped = grid.snap(Vec3::new(1.2, 3.3, -0.8));
+
+        assert_eq!(snapped, Vec3::new(1.0, 3.3, -1.0));
+    }
+
+    #[test]
+    fn snap_ignores_non_positive_spacing() {
+        let position = Vec3::new(1.2, 3.3, -0.8);
+
+        for spacing in [0.0, -1.0] {
+            let grid = GridSettings {
+                spacing,
+                ..Default::default()
+            };
+
+            assert_eq!(grid.snap(position), position);
+        }
+    }
+}
@@ -0,0 +1,113 @@
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+use tracing::{error, info};
+
+use crate::editor::{OrbitCamera, Selection};
+use crate::scene::{SceneGraph, SceneHandle};
+
+/// Bumped whenever the layout changes incompatibly. Layouts of other
+/// versions are ignored rather than half-applied.
+const LAYOUT_VERSION: u32 = 2;
+
+/// Editor state that's kept between sessions. Scenes are referred to by
+/// their position in the scene graph and nodes by their path from the root,
+/// so anything that no longer exists is dropped on load.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EditorLayout {
+    pub version: u32,
+    pub viewports: Vec<ViewportLayout>,
+    pub selection: Option<SelectionLayout>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ViewportLayout {
+    pub pane: u32,
+    pub scene: u32,
+    pub camera: OrbitCamera,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SelectionLayout {
+    pub scene: u32,
+    /// See [`crate::scene::Scene::node_path`].
+    pub node: Vec<u32>,
+}
+
+impl EditorLayout {
+    /// Reads the layout saved by the previous session, or an empty one if
+    /// there's none or it can't be used.
+    pub fn load() -> Self {
+        let Ok(data) = std::fs::read(layout_path()) else {
+            return Self::default();
+        };
+
+        match serde_json::from_slice::<EditorLayout>(&data) {
+            Ok(layout) if layout.version == LAYOUT_VERSION => layout,
+            Ok(layout) => {
+                info!(
+                    version = layout.version,
+                    "ignoring editor layout of another version"
+                );
+                Self::default()
+            }
+            Err(err) => {
+                error!(%err, "failed to read editor layout");
+                Self::default()
+            }
+        }
+    }
+
+    pub fn save(&self) {
+        let layout = EditorLayout {
+            version: LAYOUT_VERSION,
+            ..self.clone()
+        };
+
+        let data = serde_json::to_string_pretty(&layout).unwrap();
+
+        if let Err(err) = std::fs::write(layout_path(), data) {
+            error!(%err, "failed to save editor layout");
+        }
+    }
+
+    /// Camera of viewport pane `pane`, as long as it still shows the same
+    /// scene.
+    pub fn viewport_camera(&self, pane: u32, scene: u32) -> Option<&OrbitCamera> {
+        self.viewports
+            .iter()
+            .find(|viewport| viewport.pane == pane && viewport.scene == scene)
+            .map(|viewport| &viewport.camera)
+    }
+
+    pub fn selection(&self, sg: &SceneGraph) -> Option<Selection> {
+        let saved = self.selection.as_ref()?;
+        let (handle, scene) = sg.scenes().nth(saved.scene as usize)?;
+
+        Some(Selection {
+            scene: handle,
+            node: scene.node_at_path(&saved.node)?,
+        })
+    }
+
+    pub fn set_selection(&mut self, selection: Option<Selection>, sg: &SceneGraph) {
+        self.selection = selection.and_then(|selection| {
+            Some(SelectionLayout {
+                scene: scene_position(sg, selection.scene)?,
+                node: sg.scene(selection.scene)?.node_path(selection.node)?,
+            })
+        });
+    }
+}
+
+/// Position of `scene` among [`SceneGraph::scenes`].
+pub fn scene_position(sg: &SceneGraph, scene: SceneHandle) -> Option<u32> {
+    sg.scenes()
+        .position(|(handle, _)| handle == scene)
+        .map(|position| position as u32)
+}
+
+fn layout_path() -> PathBuf {
+    PathBuf::from("videoland.editor.json")
+}
@@ -1,12 +1,34 @@
+mod autosave;
+mod grid;
+mod layout;
+mod orbit;
+mod outline;
+
+use std::time::Duration;
+
 use egui::{
-    menu, pos2, Align, CentralPanel, Color32, Frame, Layout, Rect, Sense, SidePanel, TopBottomPanel,
+    menu, pos2, Align, CentralPanel, Color32, DragValue, Frame, Key, Layout, PointerButton, Rect,
+    Sense, SidePanel, TopBottomPanel,
 };
+use glam::vec2;
 
+use crate::color::Color;
 use crate::core::{Defer, Res, ResMut};
-use crate::render::{Extent2D, Renderer};
-use crate::scene::{SceneGraph, SceneHandle};
+use crate::loader::{ImportState, Loader};
+use crate::math::{Aabb, Ray};
+use crate::render::{DebugDraw, Extent2D, Pick, Renderer, MAX_ANISOTROPY};
+use crate::scene::{NodeHandle, SceneGraph, SceneHandle};
+use crate::settings::Settings;
+use crate::time::Time;
 use crate::ui::Ui;
 
+pub use self::autosave::*;
+pub use self::grid::*;
+pub use self::layout::*;
+pub use self::orbit::*;
+
+use self::outline::Outline;
+
 pub enum EditorState {
     Show,
     Hide,
@@ -14,8 +36,11 @@ pub enum EditorState {
 
 enum EditorPane {
     Viewport {
+        /// Identifies the pane in [`EditorLayout`].
+        pane: u32,
         scene_id: SceneHandle,
         texture_id: egui::TextureId,
+        camera: OrbitCamera,
     },
 }
 
@@ -27,9 +52,34 @@ impl EditorPane {
     }
 }
 
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Selection {
+    pub scene: SceneHandle,
+    pub node: NodeHandle,
+}
+
 struct Behavior<'a> {
     renderer: &'a mut Renderer,
     sg: &'a mut SceneGraph,
+    selection: &'a mut Option<Selection>,
+    isolate: bool,
+}
+
+impl Behavior<'_> {
+    /// Bounds to frame in a viewport of `scene_id`: the selection if it's in
+    /// that scene, otherwise the whole scene.
+    fn frame_bounds(&self, scene_id: SceneHandle) -> Aabb {
+        let scene = self.sg.scene(scene_id).unwrap();
+
+        let Some(selection) = self.selection.filter(|s| s.scene == scene_id) else {
+            return scene.bounds();
+        };
+
+        scene.mesh_bounds(selection.node).unwrap_or_else(|| {
+            let position = scene.world_transform(selection.node).position;
+            Aabb::new(position - 0.5, position + 0.5)
+        })
+    }
 }
 
 impl<'a> egui_tiles::Behavior<EditorPane> for Behavior<'a> {
@@ -54,19 +104,56 @@ impl<'a> egui_tiles::Behavior<EditorPane> for Behavior<'a> {
             EditorPane::Viewport {
                 scene_id,
                 texture_id,
+                camera,
+                ..
             } => {
                 let (resp, painter) =
                     ui.allocate_painter(ui.available_size(), Sense::click_and_drag());
 
+                // the viewport rect is in points, render at physical resolution
+                let pixels_per_point = ui.ctx().pixels_per_point();
                 let extent = Extent2D {
-                    width: resp.rect.width() as u32,
-                    height: resp.rect.height() as u32,
+                    width: (resp.rect.width() * pixels_per_point) as u32,
+                    height: (resp.rect.height() * pixels_per_point) as u32,
                 };
 
                 let scene = self.sg.scene(*scene_id).unwrap();
 
-                self.renderer
-                    .render_scene_to_egui_texture(*texture_id, extent, scene);
+                // isolation only filters what's drawn, node visibility is
+                // left alone
+                let root = match *self.selection {
+                    Some(selection) if self.isolate && selection.scene == *scene_id => {
+                        selection.node
+                    }
+                    _ => scene.root(),
+                };
+
+                // middle-drag orbits, shift-middle-drag pans, scrolling zooms
+                if resp.dragged_by(PointerButton::Middle) {
+                    let delta = vec2(resp.drag_delta().x, resp.drag_delta().y);
+
+                    if ui.input(|input| input.modifiers.shift) {
+                        camera.pan(delta, resp.rect.height());
+                    } else {
+                        camera.orbit(delta);
+                    }
+                }
+
+                if resp.hovered() {
+                    camera.zoom(ui.input(|input| input.smooth_scroll_delta.y));
+
+                    if ui.input(|input| input.key_pressed(Key::F)) {
+                        camera.frame(self.frame_bounds(*scene_id));
+                    }
+                }
+
+                self.renderer.render_scene_to_egui_texture(
+                    *texture_id,
+                    extent,
+                    scene,
+                    root,
+                    &camera.camera(),
+                );
 
                 let uv = Rect {
                     min: pos2(0.0, 0.0),
@@ -75,7 +162,39 @@ impl<'a> egui_tiles::Behavior<EditorPane> for Behavior<'a> {
 
                 painter.image(*texture_id, resp.rect, uv, Color32::WHITE);
 
-                ui.allocate_new_ui(egui::UiBuilder::new().max_rect(resp.rect), |ui: &mut egui::Ui| ui.button("text"));
+                if resp.clicked() {
+                    if let Some(pointer) = resp.interact_pointer_pos() {
+                        let position = (pointer - resp.rect.min) * pixels_per_point;
+                        let picked = self.renderer.pick_object(
+                            *texture_id,
+                            position.x as u32,
+                            position.y as u32,
+                        );
+
+                        let node = match picked {
+                            Some(Pick::Object(index)) => scene.node_at_index(index),
+                            Some(Pick::Background) => None,
+                            // no render target to read back from, fall back
+                            // to mesh bounds
+                            None => {
+                                let uv = (pointer - resp.rect.min) / resp.rect.size();
+                                let ndc = vec2(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0);
+                                let inverse_view_projection = camera
+                                    .camera()
+                                    .view_projection(extent.aspect_ratio())
+                                    .inverse();
+
+                                let ray = Ray::from_ndc(ndc, inverse_view_projection);
+                                scene.raycast(root, &ray).map(|(node, _)| node)
+                            }
+                        };
+
+                        *self.selection = node.map(|node| Selection {
+                            scene: *scene_id,
+                            node,
+                        });
+                    }
+                }
             }
         }
 
@@ -86,20 +205,100 @@ impl<'a> egui_tiles::Behavior<EditorPane> for Behavior<'a> {
 pub struct Editor {
     tree: egui_tiles::Tree<EditorPane>,
     search: String,
+    selection: Option<Selection>,
+    isolate: bool,
+    outline: Outline,
+}
+
+impl Editor {
+    pub fn selection(&self) -> Option<Selection> {
+        self.selection
+    }
+
+    pub fn layout(&self, sg: &SceneGraph) -> EditorLayout {
+        let mut layout = EditorLayout::default();
+
+        for (_, tile) in self.tree.tiles.iter() {
+            if let egui_tiles::Tile::Pane(EditorPane::Viewport {
+                pane,
+                scene_id,
+                camera,
+                ..
+            }) = tile
+            {
+                let Some(scene) = scene_position(sg, *scene_id) else {
+                    continue;
+                };
+
+                layout.viewports.push(ViewportLayout {
+                    pane: *pane,
+                    scene,
+                    camera: camera.clone(),
+                });
+            }
+        }
+
+        layout.set_selection(self.selection, sg);
+        layout
+    }
+
+    /// Moves viewport cameras to where `layout` has them and replaces the
+    /// selection with its one, if that still exists.
+    pub fn apply_layout(&mut self, layout: &EditorLayout, sg: &SceneGraph) {
+        for (_, tile) in self.tree.tiles.iter_mut() {
+            if let egui_tiles::Tile::Pane(EditorPane::Viewport {
+                pane,
+                scene_id,
+                camera,
+                ..
+            }) = tile
+            {
+                let saved = scene_position(sg, *scene_id)
+                    .and_then(|scene| layout.viewport_camera(*pane, scene));
+
+                if let Some(saved) = saved {
+                    *camera = saved.clone();
+                }
+            }
+        }
+
+        self.selection = layout.selection(sg);
+    }
+
+    /// Saves viewport cameras and the selection for the next session.
+    pub fn save_layout(&self, sg: &SceneGraph) {
+        self.layout(sg).save();
+    }
 }
 
 pub fn init(mut defer: Defer, mut renderer: ResMut<Renderer>, g: Res<SceneGraph>) {
+    let layout = EditorLayout::load();
     let mut tiles = egui_tiles::Tiles::default();
 
     let main_panes = g
         .scenes()
-        .map(|(scene_id, _)| {
+        .enumerate()
+        .map(|(position, (scene_id, scene))| {
+            // one pane per scene for now
+            let pane = position as u32;
+
+            let camera = match layout.viewport_camera(pane, position as u32) {
+                Some(camera) => camera.clone(),
+                None => {
+                    let mut camera = OrbitCamera::new();
+                    camera.frame(scene.bounds());
+                    camera
+                }
+            };
+
             tiles.insert_pane(EditorPane::Viewport {
+                pane,
                 scene_id,
                 texture_id: renderer.create_egui_render_target(Extent2D {
                     width: 256,
                     height: 256,
                 }),
+                camera,
             })
         })
         .collect();
@@ -110,8 +309,33 @@ pub fn init(mut defer: Defer, mut renderer: ResMut<Renderer>, g: Res<SceneGraph>
     defer.insert(Editor {
         tree,
         search: "".to_owned(),
+        selection: layout.selection(&g),
+        isolate: false,
+        outline: Outline::default(),
     });
     defer.insert(EditorState::Show);
+    defer.insert(AutoSave::new());
+}
+
+pub fn autosave(
+    settings: Res<Settings>,
+    sg: Res<SceneGraph>,
+    editor: Res<Editor>,
+    mut autosave: ResMut<AutoSave>,
+) {
+    let interval = Duration::from_secs_f32(settings.autosave_interval_s.max(0.0));
+
+    autosave.save_if_due(interval, &sg, &editor);
+}
+
+pub fn draw_grid(
+    editor_state: Res<EditorState>,
+    settings: Res<Settings>,
+    mut debug_draw: ResMut<DebugDraw>,
+) {
+    if let EditorState::Show = *editor_state {
+        settings.editor_grid.draw(&mut debug_draw);
+    }
 }
 
 pub fn show(
@@ -119,18 +343,53 @@ pub fn show(
     mut editor: ResMut<Editor>,
     mut renderer: ResMut<Renderer>,
     mut sg: ResMut<SceneGraph>,
+    mut loader: ResMut<Loader>,
+    mut settings: ResMut<Settings>,
+    mut autosave: ResMut<AutoSave>,
+    time: Res<Time>,
     ui: Res<Ui>,
 ) {
     if let EditorState::Hide = *editor_state {
         return;
     }
 
+    if !ui.is_frame_active() {
+        return;
+    }
+
+    let editor = &mut *editor;
+
+    if autosave.has_recovery() {
+        egui::Window::new("recover")
+            .collapsible(false)
+            .resizable(false)
+            .show(ui.ctx(), |ui| {
+                ui.label("The previous session didn't exit cleanly. Restore its auto-save?");
+
+                ui.horizontal(|ui| {
+                    if ui.button("restore").clicked() {
+                        autosave.restore(&mut sg, editor);
+                        autosave.discard_recovery();
+                    }
+
+                    if ui.button("discard").clicked() {
+                        autosave.discard_recovery();
+                    }
+                });
+            });
+    }
+
     TopBottomPanel::top("vl-editor-top-panel").show(ui.ctx(), |ui| {
         ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
             if ui.button("hide").clicked() {
                 *editor_state = EditorState::Hide;
             }
 
+            ui.add_enabled_ui(editor.selection.is_some(), |ui| {
+                ui.toggle_value(&mut editor.isolate, "isolate")
+                    .on_hover_text("render only the selected node and its children");
+            });
+
             ui.with_layout(Layout::left_to_right(Align::Center), |ui| {
                 menu::bar(ui, |ui| {
                     ui.menu_button("File", |ui| {
@@ -150,9 +409,212 @@ pub fn show(
     });
 
     SidePanel::left("vl-explorer").show(ui.ctx(), |ui| {
-        ui.label("do stuff");
+        ui.collapsing("stats", |ui| {
+            egui::Grid::new("vl-frame-times").show(ui, |ui| {
+                let stats = [
+                    ("avg", time.avg_ms()),
+                    ("min", time.min_ms()),
+                    ("max", time.max_ms()),
+                    ("p95", time.p95_ms()),
+                    ("p99", time.p99_ms()),
+                ];
+
+                for (label, ms) in stats {
+                    ui.label(label);
+                    ui.label(format!("{ms:.2} ms"));
+                    ui.end_row();
+                }
+
+                ui.label("draw calls");
+                ui.label(renderer.draw_calls().to_string());
+                ui.end_row();
+
+                let meshes = renderer.mesh_arena_stats();
+                let mib = |bytes: u64| bytes as f64 / (1024.0 * 1024.0);
+
+                ui.label("mesh memory");
+                ui.label(format!(
+                    "{:.1} / {:.1} MiB",
+                    mib(meshes.bytes_used()),
+                    mib(meshes.bytes_total())
+                ));
+                ui.end_row();
+            });
+
+            let adapter = renderer.adapter_info();
+
+            ui.separator();
+            ui.label(&adapter.name);
+            ui.label(format!("{:?}, {}", adapter.backend, adapter.driver_info));
+        });
+
+        ui.collapsing("rendering", |ui| {
+            ui.horizontal(|ui| {
+                let anisotropy = ui.add(
+                    DragValue::new(&mut settings.texture_anisotropy)
+                        .range(1..=MAX_ANISOTROPY)
+                        .suffix("x"),
+                );
+
+                if anisotropy.changed() {
+                    renderer.set_anisotropy(settings.texture_anisotropy);
+                }
+
+                ui.label("anisotropy");
+            });
+
+            if ui
+                .checkbox(&mut settings.depth_prepass, "depth prepass")
+                .changed()
+            {
+                renderer.set_depth_prepass(settings.depth_prepass);
+            }
+        });
+
+        ui.collapsing("grid", |ui| {
+            let grid = &mut settings.editor_grid;
+
+            ui.checkbox(&mut grid.visible, "visible");
+
+            ui.horizontal(|ui| {
+                ui.add(
+                    DragValue::new(&mut grid.spacing)
+                        .speed(0.01)
+                        .range(0.01..=100.0),
+                );
+                ui.label("spacing");
+            });
+
+            ui.horizontal(|ui| {
+                ui.add(DragValue::new(&mut grid.major_interval).range(0..=100));
+                ui.label("major interval");
+            });
+
+            ui.horizontal(|ui| {
+                ui.add(DragValue::new(&mut grid.extent).range(1.0..=1000.0));
+                ui.label("extent");
+            });
+        });
+
+        for (index, (_, scene)) in sg.scenes_mut().enumerate() {
+            ui.push_id(index, |ui| {
+                ui.collapsing("scene", |ui| {
+                    ui.horizontal(|ui| {
+                        let mut bg_color = scene.bg_color().to_array();
+
+                        if ui
+                            .color_edit_button_rgba_unmultiplied(&mut bg_color)
+                            .changed()
+                        {
+                            scene.set_bg_color(Color::from_array(bg_color));
+                        }
+
+                        ui.label("background");
+                    });
+
+                    if let Some(camera_id) = scene.primary_camera_id() {
+                        if ui.button("frame all").clicked() {
+                            let bounds = scene.bounds();
+                            scene.node_mut(camera_id).camera_mut().frame(bounds);
+                        }
+                    }
+
+                    let lighting = scene.lighting_mut();
+
+                    ui.horizontal(|ui| {
+                        let mut ambient_color = lighting.ambient_color.to_array();
+
+                        if ui
+                            .color_edit_button_rgba_unmultiplied(&mut ambient_color)
+                            .changed()
+                        {
+                            lighting.ambient_color = Color::from_array(ambient_color);
+                        }
+
+                        ui.label("ambient");
+                    });
+
+                    ui.horizontal(|ui| {
+                        ui.add(
+                            DragValue::new(&mut lighting.ambient_intensity)
+                                .speed(0.01)
+                                .range(0.0..=16.0),
+                        );
+                        ui.label("ambient intensity");
+                    });
+
+                    ui.horizontal(|ui| {
+                        ui.add(
+                            DragValue::new(&mut lighting.exposure)
+                                .speed(0.01)
+                                .range(0.0..=16.0),
+                        );
+                        ui.label("exposure");
+                    });
+                });
+            });
+        }
+
+        if let Some(selection) = editor.selection {
+            ui.collapsing("transform", |ui| {
+                transform_ui(ui, &mut sg, selection, &settings.editor_grid);
+            });
+        }
+
+        ui.separator();
+
+        editor.outline.show(ui, &sg, &mut editor.selection);
     });
 
+    let import_status = loader.import_status_mut();
+
+    if !import_status.is_empty() {
+        TopBottomPanel::bottom("vl-imports").show(ui.ctx(), |ui| {
+            ui.horizontal(|ui| {
+                ui.label("imports");
+
+                if ui.button("dismiss finished").clicked() {
+                    import_status.dismiss_finished();
+                }
+            });
+
+            let mut dismissed = None;
+
+            for entry in import_status.entries() {
+                ui.horizontal(|ui| {
+                    if ui.small_button("x").clicked() {
+                        dismissed = Some(entry.id);
+                    }
+
+                    ui.label(&entry.path);
+
+                    match &entry.state {
+                        ImportState::Loading(progress) => {
+                            ui.spinner();
+
+                            if progress.mesh_count > 0 {
+                                ui.label(format!(
+                                    "{}/{} meshes",
+                                    progress.meshes_done, progress.mesh_count
+                                ))
+                            } else {
+                                ui.label("parsing")
+                            }
+                        }
+                        ImportState::Done => ui.label("ok"),
+                        ImportState::Failed(message) => {
+                            ui.colored_label(ui.visuals().error_fg_color, message)
+                        }
+                    };
+                });
+            }
+
+            if let Some(id) = dismissed {
+                import_status.dismiss(id);
+            }
+        });
+    }
+
     CentralPanel::default()
         .frame(Frame::none())
         .show(ui.ctx(), |ui| {
@@ -160,8 +622,36 @@ pub fn show(
                 &mut Behavior {
                     renderer: &mut renderer,
                     sg: &mut sg,
+                    selection: &mut editor.selection,
+                    isolate: editor.isolate,
                 },
                 ui,
             )
         });
 }
+
+fn transform_ui(ui: &mut egui::Ui, sg: &mut SceneGraph, selection: Selection, grid: &GridSettings) {
+    let Some(scene) = sg.scene_mut(selection.scene) else {
+        return;
+    };
+
+    let mut position = scene.node(selection.node).transform.position;
+    let mut changed = false;
+
+    ui.horizontal(|ui| {
+        for value in [&mut position.x, &mut position.y, &mut position.z] {
+            changed |= ui.add(DragValue::new(value).speed(0.01)).changed();
+        }
+        ui.label("position");
+    });
+
+    if ui.button("snap to grid").clicked() {
+        position = grid.snap(position);
+        changed = true;
+    }
+
+    // only touch the transform on edits, it marks the node dirty
+    if changed {
+        scene.node_mut(selection.node).transform_mut().position = position;
+    }
+}
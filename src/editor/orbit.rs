@@ -0,0 +1,105 @@
+use glam::{Vec2, Vec3};
+
+use crate::math::Aabb;
+use crate::scene::Camera;
+
+/// Degrees of rotation per point dragged.
+const ORBIT_SPEED: f32 = 0.3;
+/// Fraction of the distance zoomed per point scrolled.
+const ZOOM_SPEED: f32 = 0.002;
+const MIN_DISTANCE: f32 = 0.01;
+const MAX_PITCH: f32 = 89.0;
+
+/// Editor viewport camera circling around a focus point, independent of the
+/// scene's own cameras.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct OrbitCamera {
+    pub focus: Vec3,
+    pub distance: f32,
+    /// Degrees, positive looks up.
+    pub pitch: f32,
+    /// Degrees.
+    pub yaw: f32,
+    pub fov: f32,
+}
+
+impl OrbitCamera {
+    pub fn new() -> Self {
+        Self {
+            focus: Vec3::ZERO,
+            distance: 5.0,
+            pitch: -20.0,
+            yaw: 0.0,
+            fov: 75.0,
+        }
+    }
+
+    /// Rotates around the focus by a pointer drag of `delta` points.
+    pub fn orbit(&mut self, delta: Vec2) {
+        self.yaw += delta.x * ORBIT_SPEED;
+        self.pitch = (self.pitch - delta.y * ORBIT_SPEED).clamp(-MAX_PITCH, MAX_PITCH);
+    }
+
+    /// Moves the focus so that it follows a pointer drag of `delta` points
+    /// in a viewport `viewport_height` points tall.
+    pub fn pan(&mut self, delta: Vec2, viewport_height: f32) {
+        let camera = self.camera();
+        let (look, right) = camera.forward_right();
+        let up = right.cross(look);
+
+        // world units per point at the focus distance
+        let scale = 2.0 * self.distance * (self.fov.to_radians() * 0.5).tan() / viewport_height;
+
+        self.focus += (up * delta.y - right * delta.x) * scale;
+    }
+
+    /// Moves towards the focus for positive `scroll`, in points.
+    pub fn zoom(&mut self, scroll: f32) {
+        self.distance = (self.distance * (-scroll * ZOOM_SPEED).exp()).max(MIN_DISTANCE);
+    }
+
+    /// Focuses on the center of `bounds`, backing off until they fit in the
+    /// vertical field of view.
+    pub fn frame(&mut self, bounds: Aabb) {
+        let radius = bounds.half_extents().length().max(MIN_DISTANCE);
+
+        self.focus = bounds.center();
+        self.distance = radius / (self.fov.to_radians() * 0.5).sin();
+    }
+
+    pub fn camera(&self) -> Camera {
+        let mut camera = Camera {
+            position: Vec3::ZERO,
+            pitch: self.pitch,
+            yaw: self.yaw,
+            fov: self.fov,
+        };
+
+        let (look, _) = camera.forward_right();
+        camera.position = self.focus - look * self.distance;
+
+        camera
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn camera_looks_at_focus() {
+        let mut orbit = OrbitCamera::new();
+        orbit.focus = Vec3::new(1.0, 2.0, 3.0);
+        orbit.orbit(Vec2::new(100.0, -40.0));
+        orbit.zoom(200.0);
+
+        let camera = orbit.camera();
+        let (look, _) = camera.forward_right();
+
+        assert!((camera.position.distance(orbit.focus) - orbit.distance).abs() < 1e-4);
+        assert!((orbit.focus - camera.position)
+            .normalize()
+            .abs_diff_eq(look, 1e-5));
+        assert!(orbit.distance < 5.0);
+    }
+}
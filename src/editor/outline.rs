@@ -0,0 +1,176 @@
+use ahash::AHashSet;
+use egui::{Key, ScrollArea};
+
+use crate::editor::Selection;
+use crate::scene::{Node, SceneGraph};
+
+/// Expanded nodes and selection for the scene hierarchy in the explorer.
+#[derive(Default)]
+pub(super) struct Outline {
+    expanded: AHashSet<Selection>,
+}
+
+struct Row {
+    item: Selection,
+    parent: Option<Selection>,
+    depth: usize,
+    has_children: bool,
+}
+
+fn node_label(node: &Node) -> &'static str {
+    match node {
+        Node::Pivot(_) => "pivot",
+        Node::Mesh(_) => "mesh",
+        Node::Camera(_) => "camera",
+    }
+}
+
+impl Outline {
+    /// Rows in display order, skipping the children of collapsed nodes.
+    fn visible_rows(&self, sg: &SceneGraph) -> Vec<Row> {
+        let mut rows = Vec::new();
+
+        for (scene_id, scene) in sg.scenes() {
+            let mut stack = vec![(scene.root(), None, 0)];
+
+            while let Some((node, parent, depth)) = stack.pop() {
+                let item = Selection {
+                    scene: scene_id,
+                    node,
+                };
+                let children = scene.node(node).children;
+
+                rows.push(Row {
+                    item,
+                    parent,
+                    depth,
+                    has_children: !children.is_empty(),
+                });
+
+                if self.expanded.contains(&item) {
+                    // reversed, so that the first child is popped first
+                    for &child in children.iter().rev() {
+                        stack.push((child, Some(item), depth + 1));
+                    }
+                }
+            }
+        }
+
+        rows
+    }
+
+    pub(super) fn show(
+        &mut self,
+        ui: &mut egui::Ui,
+        sg: &SceneGraph,
+        selection: &mut Option<Selection>,
+    ) {
+        let rows = self.visible_rows(sg);
+
+        let navigated = if ui.ctx().wants_keyboard_input() {
+            false
+        } else {
+            self.navigate(ui.ctx(), &rows, selection)
+        };
+
+        ScrollArea::vertical().id_salt("vl-outline").show(ui, |ui| {
+            for row in &rows {
+                ui.horizontal(|ui| {
+                    ui.add_space(row.depth as f32 * 12.0);
+
+                    if row.has_children {
+                        let expanded = self.expanded.contains(&row.item);
+
+                        if ui.small_button(if expanded { "-" } else { "+" }).clicked() {
+                            self.toggle(row.item);
+                        }
+                    }
+
+                    let scene = sg.scene(row.item.scene).unwrap();
+                    let label = format!(
+                        "{} {}",
+                        node_label(&scene.node(row.item.node)),
+                        row.item.node.index()
+                    );
+
+                    let selected = *selection == Some(row.item);
+                    let response = ui.selectable_label(selected, label);
+
+                    if response.clicked() {
+                        *selection = Some(row.item);
+                    }
+
+                    if selected && navigated {
+                        response.scroll_to_me(None);
+                    }
+                });
+            }
+        });
+    }
+
+    /// Moves the selection with the arrow keys. Returns whether it changed.
+    fn navigate(
+        &mut self,
+        ctx: &egui::Context,
+        rows: &[Row],
+        selection: &mut Option<Selection>,
+    ) -> bool {
+        let (up, down, left, right) = ctx.input(|input| {
+            (
+                input.key_pressed(Key::ArrowUp),
+                input.key_pressed(Key::ArrowDown),
+                input.key_pressed(Key::ArrowLeft),
+                input.key_pressed(Key::ArrowRight),
+            )
+        });
+
+        if !(up || down || left || right) {
+            return false;
+        }
+
+        let current =
+            selection.and_then(|selection| rows.iter().position(|row| row.item == selection));
+
+        let Some(current) = current else {
+            *selection = rows.first().map(|row| row.item);
+            return selection.is_some();
+        };
+
+        let row = &rows[current];
+        let expanded = self.expanded.contains(&row.item);
+
+        let next = if up {
+            current.checked_sub(1)
+        } else if down {
+            Some(current + 1).filter(|&next| next < rows.len())
+        } else if right && row.has_children && !expanded {
+            self.expanded.insert(row.item);
+            None
+        } else if right && expanded {
+            // the first child comes right after its parent
+            Some(current + 1).filter(|&next| next < rows.len())
+        } else if left && expanded {
+            self.expanded.remove(&row.item);
+            None
+        } else if left {
+            row.parent
+                .and_then(|parent| rows.iter().position(|row| row.item == parent))
+        } else {
+            None
+        };
+
+        match next {
+            Some(next) => {
+                *selection = Some(rows[next].item);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn toggle(&mut self, item: Selection) {
+        if !self.expanded.remove(&item) {
+            self.expanded.insert(item);
+        }
+    }
+}
@@ -1,25 +1,123 @@
 use ahash::AHashSet;
 use glam::{vec2, Vec2};
+use tracing::warn;
+use winit::dpi::PhysicalPosition;
 use winit::event::{DeviceEvent, ElementState, KeyEvent, MouseButton, WindowEvent};
 use winit::keyboard::{KeyCode, PhysicalKey};
+use winit::window::{CursorGrabMode, Window};
+
+use crate::core::{Res, ResMut};
+
+/// How the cursor behaves over the window. Change the resource and
+/// [`apply_cursor_mode`] takes care of the rest.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum CursorMode {
+    /// Visible and free to leave the window.
+    #[default]
+    Free,
+
+    /// Hidden and held in place, for mouse look. Only mouse motion deltas
+    /// are meaningful.
+    Locked,
+
+    /// Visible but kept inside the window.
+    Confined,
+}
+
+/// The [`CursorMode`] that's currently applied to the window.
+pub struct CursorState {
+    applied: Option<CursorMode>,
+    emulate_lock: bool,
+}
+
+impl CursorState {
+    pub fn new() -> Self {
+        Self {
+            applied: None,
+            emulate_lock: false,
+        }
+    }
+}
+
+/// Applies changes to [`CursorMode`] to the window. Where locking isn't
+/// supported (e.g. on X11), the cursor is confined and re-centered every
+/// frame instead.
+pub fn apply_cursor_mode(
+    window: Res<Window>,
+    mode: Res<CursorMode>,
+    mut state: ResMut<CursorState>,
+) {
+    if state.applied != Some(*mode) {
+        state.emulate_lock = false;
+
+        let grab = match *mode {
+            CursorMode::Free => window.set_cursor_grab(CursorGrabMode::None),
+            CursorMode::Confined => window.set_cursor_grab(CursorGrabMode::Confined),
+            CursorMode::Locked => window.set_cursor_grab(CursorGrabMode::Locked).or_else(|_| {
+                state.emulate_lock = true;
+                window.set_cursor_grab(CursorGrabMode::Confined)
+            }),
+        };
+
+        if let Err(err) = grab {
+            warn!(mode = ?*mode, %err, "failed to grab cursor");
+        }
+
+        window.set_cursor_visible(*mode != CursorMode::Locked);
+        state.applied = Some(*mode);
+    }
+
+    if state.emulate_lock {
+        let size = window.inner_size();
+        let center = PhysicalPosition::new(size.width / 2, size.height / 2);
+
+        // not fatal, the cursor just drifts until it hits the window edge
+        let _ = window.set_cursor_position(center);
+    }
+}
 
 pub struct InputState {
     held_keys: AHashSet<KeyCode>,
+    just_pressed_keys: AHashSet<KeyCode>,
     held_mouse_buttons: AHashSet<MouseButton>,
 
     mouse_delta_since_last_frame: Vec2,
+
+    ui_wants_keyboard: bool,
+    ui_wants_pointer: bool,
 }
 
 impl InputState {
     pub fn new() -> Self {
         Self {
             held_keys: AHashSet::new(),
+            just_pressed_keys: AHashSet::new(),
             held_mouse_buttons: AHashSet::new(),
 
             mouse_delta_since_last_frame: Vec2::ZERO,
+
+            ui_wants_keyboard: false,
+            ui_wants_pointer: false,
         }
     }
 
+    /// Whether the UI currently takes keyboard and pointer input, e.g. while
+    /// a text field is focused. Key and mouse button presses are ignored
+    /// while it does, releases still go through so nothing gets stuck.
+    pub fn set_ui_focus(&mut self, keyboard: bool, pointer: bool) {
+        self.ui_wants_keyboard = keyboard;
+        self.ui_wants_pointer = pointer;
+    }
+
+    /// Whether the UI has keyboard focus, see [`InputState::set_ui_focus`].
+    pub fn ui_has_focus(&self) -> bool {
+        self.ui_wants_keyboard
+    }
+
+    pub fn ui_wants_pointer(&self) -> bool {
+        self.ui_wants_pointer
+    }
+
     pub fn submit_window_input(&mut self, input: &WindowEvent) {
         match input {
             WindowEvent::KeyboardInput { event, .. } => {
@@ -42,14 +140,30 @@ impl InputState {
         }
     }
 
+    /// Raw mouse motion since the previous frame, positive `y` is down.
+    /// Keeps counting while the cursor is locked.
+    pub fn mouse_delta(&self) -> Vec2 {
+        self.mouse_delta_since_last_frame
+    }
+
     pub fn reset_mouse_movement(&mut self) {
         self.mouse_delta_since_last_frame = Vec2::ZERO;
     }
 
+    pub fn reset_just_pressed_keys(&mut self) {
+        self.just_pressed_keys.clear();
+    }
+
     pub fn is_key_pressed(&self, key: KeyCode) -> bool {
         self.held_keys.contains(&key)
     }
 
+    /// Whether the key went down during the current step. Key repeats don't
+    /// count.
+    pub fn is_key_just_pressed(&self, key: KeyCode) -> bool {
+        self.just_pressed_keys.contains(&key)
+    }
+
     pub fn is_mouse_button_pressed(&self, key: MouseButton) -> bool {
         self.held_mouse_buttons.contains(&key)
     }
@@ -61,7 +175,12 @@ impl InputState {
         };
 
         match input.state {
+            ElementState::Pressed if self.ui_wants_keyboard => {}
             ElementState::Pressed => {
+                if !input.repeat {
+                    self.just_pressed_keys.insert(key_code);
+                }
+
                 self.held_keys.insert(key_code);
             }
             ElementState::Released => {
@@ -72,6 +191,7 @@ impl InputState {
 
     fn submit_mouse_input(&mut self, state: ElementState, button: MouseButton) {
         match state {
+            ElementState::Pressed if self.ui_wants_pointer => {}
             ElementState::Pressed => {
                 self.held_mouse_buttons.insert(button);
             }
@@ -1,12 +1,16 @@
 #![allow(dead_code)]
 #![allow(unused_variables)]
 #![allow(clippy::new_without_default)]
+#![allow(clippy::too_many_arguments)]
 
 pub mod asset;
+pub mod color;
 pub mod core;
 pub mod editor;
 pub mod input;
 pub mod loader;
+pub mod math;
+pub mod profile;
 pub mod render;
 pub mod scene;
 pub mod settings;
@@ -14,30 +18,42 @@ pub mod sys;
 pub mod time;
 pub mod ui;
 
-pub use glam as math;
 pub use tracing as log;
 pub use uuid;
 pub use winit;
 use winit::application::ApplicationHandler;
 
+use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use rayon::ThreadPoolBuilder;
-use winit::event::{DeviceEvent, KeyEvent, WindowEvent};
+use tracing::error;
+use winit::event::{DeviceEvent, ElementState, KeyEvent, WindowEvent};
 use winit::event_loop::{ActiveEventLoop, ControlFlow, EventLoop};
 use winit::window::Window;
 
 use crate::asset::{ShaderStage, Vfs};
 use crate::core::{Registry, Schedule, Stage};
-use crate::input::InputState;
-use crate::loader::{Loader, ShaderBytecode, ShaderCompiler};
+use crate::editor::{AutoSave, Editor};
+use crate::input::{CursorMode, CursorState, InputState};
+use crate::loader::{Loader, MeshLoaded, ShaderBytecode, ShaderCompiler};
+use crate::profile::FrameProfiler;
 use crate::render::PreparedUi;
-use crate::render::{Extent2D, Renderer};
-use crate::scene::SceneGraph;
+use crate::render::{DebugDraw, Extent2D, Renderer};
+use crate::scene::{Scene, SceneGraph};
 use crate::settings::Settings;
 use crate::time::Time;
 use crate::ui::Ui;
 
+/// Emitted whenever the window is resized, including to zero while it's
+/// minimized. Sizes are in physical pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WindowResized {
+    pub width: u32,
+    pub height: u32,
+}
+
 #[derive(Default)]
 pub struct EngineState {
     pub quit: bool,
@@ -45,14 +61,26 @@ pub struct EngineState {
 
 struct AppState {
     reg: Registry,
-    schedule: Box<dyn Fn(&Registry) -> Schedule>,
+    schedule: Schedule,
+    focused: bool,
+    minimized: bool,
+    next_unfocused_update: Instant,
 }
 
 impl AppState {
-    fn new(window: Window) -> Self {
+    fn new(
+        window: Window,
+        initial_scene: Option<PathBuf>,
+        schedule: &dyn Fn(&Registry) -> Schedule,
+    ) -> Self {
         let settings = Settings::load_global();
 
-        let thread_pool = Arc::new(ThreadPoolBuilder::new().num_threads(4).build().unwrap());
+        let thread_pool = Arc::new(
+            ThreadPoolBuilder::new()
+                .num_threads(settings.worker_thread_count())
+                .build()
+                .unwrap(),
+        );
 
         let vfs = Arc::new(Vfs::new());
 
@@ -60,22 +88,60 @@ impl AppState {
 
         let shader_compiler = ShaderCompiler::new();
 
-        let egui_vs = shader_compiler
-            .compile_hlsl(
-                "videoland/data/shaders/egui.hlsl",
-                ShaderStage::Vertex,
-                ShaderBytecode::SpirV,
-            )
-            .unwrap();
-        let egui_fs = shader_compiler
-            .compile_hlsl(
-                "videoland/data/shaders/egui.hlsl",
+        // the error shader's vertex stage doesn't fit these pipelines, so
+        // they're left out if theirs fails to compile
+        let compile_vs = |path| match shader_compiler.compile_hlsl(
+            path,
+            ShaderStage::Vertex,
+            ShaderBytecode::SpirV,
+        ) {
+            Ok(shader) => Some(shader),
+            Err(err) => {
+                error!("{err}");
+                None
+            }
+        };
+        let compile_fs = |path| {
+            shader_compiler.compile_hlsl_or_error(
+                path,
                 ShaderStage::Fragment,
                 ShaderBytecode::SpirV,
             )
-            .unwrap();
+        };
+
+        let skybox_vs = compile_vs("videoland/data/shaders/skybox.hlsl");
+        let skybox_fs = compile_fs("videoland/data/shaders/skybox.hlsl");
+
+        let debug_vs = compile_vs("videoland/data/shaders/debug.hlsl");
+        let debug_fs = compile_fs("videoland/data/shaders/debug.hlsl");
 
-        let renderer = Renderer::new(&window, egui_vs, egui_fs);
+        let views_vs = compile_vs("videoland/data/shaders/views.hlsl");
+        let views_fs = compile_fs("videoland/data/shaders/views.hlsl");
+
+        let error_vs =
+            shader_compiler.compile_error_shader(ShaderStage::Vertex, ShaderBytecode::SpirV);
+        let error_fs =
+            shader_compiler.compile_error_shader(ShaderStage::Fragment, ShaderBytecode::SpirV);
+
+        let mut renderer = Renderer::new(&window, error_vs, error_fs);
+        renderer.set_frames_in_flight(settings.frames_in_flight);
+        renderer.set_anisotropy(settings.texture_anisotropy);
+        renderer.set_depth_prepass(settings.depth_prepass);
+        if let Some(skybox_vs) = skybox_vs {
+            if let Err(err) = renderer.init_skybox(&skybox_vs, &skybox_fs) {
+                error!("{err}");
+            }
+        }
+        if let Some(debug_vs) = debug_vs {
+            if let Err(err) = renderer.init_debug_draw(&debug_vs, &debug_fs) {
+                error!("{err}");
+            }
+        }
+        if let Some(views_vs) = views_vs {
+            if let Err(err) = renderer.init_views(&views_vs, &views_fs) {
+                error!("{err}");
+            }
+        }
         let mut ui = Ui::new(&window);
 
         ui.begin_frame(&window);
@@ -83,46 +149,82 @@ impl AppState {
         let mut reg = Registry::new();
 
         reg.register_event::<KeyEvent>();
-
-        // window.set_cursor_grab(CursorGrabMode::Confined).unwrap();
-        window.set_cursor_visible(false);
+        reg.register_event::<WindowResized>();
+        reg.register_event::<MeshLoaded>();
 
         reg.insert(InputState::new());
+        reg.insert(CursorMode::default());
+        reg.insert(CursorState::new());
         reg.insert(Time::new());
+        reg.insert(FrameProfiler::new());
         reg.insert(ui);
         reg.insert(window);
         reg.insert(Loader::new(vfs, thread_pool));
         reg.insert(settings);
         reg.insert(renderer);
         reg.insert(PreparedUi::default());
+        reg.insert(DebugDraw::new());
         reg.insert(EngineState::default());
-        reg.insert(SceneGraph::new());
+        reg.insert(load_initial_scene(initial_scene));
 
-        // schedule(&reg).execute(Stage::Init, &mut reg);
+        // built once, so that systems keep their state between steps
+        let mut schedule = schedule(&reg);
+        schedule.execute(Stage::Init, &mut reg);
 
         Self {
             reg,
-            schedule: Box::new(|_| Schedule::new()),
+            schedule,
+            focused: true,
+            minimized: false,
+            next_unfocused_update: Instant::now(),
         }
     }
 
     fn handle_window_event(&mut self, event: WindowEvent) -> EventLoopIterationDecision {
-        {
+        let ui_focus = {
             let window = self.reg.res::<Window>();
-            self.reg.res_mut::<Ui>().on_event(&window, &event);
-        }
+            let mut ui = self.reg.res_mut::<Ui>();
+            ui.on_event(&window, &event);
 
-        self.reg.res_mut::<InputState>().submit_window_input(&event);
+            (ui.wants_keyboard_input(), ui.wants_pointer_input())
+        };
+
+        {
+            let mut input = self.reg.res_mut::<InputState>();
+            input.set_ui_focus(ui_focus.0, ui_focus.1);
+            input.submit_window_input(&event);
+        }
 
         match event {
             WindowEvent::CloseRequested => return EventLoopIterationDecision::Break,
+            WindowEvent::Focused(focused) => self.focused = focused,
+            // typing into the UI shouldn't trigger anything else
+            WindowEvent::KeyboardInput { event, .. }
+                if ui_focus.0 && event.state == ElementState::Pressed => {}
             WindowEvent::KeyboardInput { event, .. } => {
                 self.reg.event_queue_mut::<KeyEvent>().emit(event);
             }
-            WindowEvent::Resized(size) => self.reg.res_mut::<Renderer>().resize(Extent2D {
-                width: size.width,
-                height: size.height,
-            }),
+            WindowEvent::Resized(size) => {
+                let size = Extent2D {
+                    width: size.width,
+                    height: size.height,
+                };
+
+                self.reg
+                    .event_queue_mut::<WindowResized>()
+                    .emit(WindowResized {
+                        width: size.width,
+                        height: size.height,
+                    });
+
+                // the swapchain keeps its old size while minimized and is
+                // resized once the window is restored
+                self.minimized = size.is_empty();
+
+                if !self.minimized {
+                    self.reg.res_mut::<Renderer>().resize(size);
+                }
+            }
             _ => {}
         }
 
@@ -135,10 +237,86 @@ impl AppState {
         EventLoopIterationDecision::Continue
     }
 
+    fn shutdown(mut self) {
+        self.schedule.execute(Stage::Shutdown, &mut self.reg);
+
+        if self.reg.contains::<AutoSave>() {
+            self.reg.res_mut::<AutoSave>().finish();
+        }
+
+        if self.reg.contains::<Editor>() {
+            let sg = self.reg.res::<SceneGraph>();
+            self.reg.res::<Editor>().save_layout(&sg);
+        }
+
+        // the surface is created from the raw window handle, so the window
+        // has to outlive the renderer, and the renderer has to outlive
+        // everything that may still own GPU resources
+        let window = self.reg.remove::<Window>();
+        let renderer = self.reg.remove::<Renderer>();
+
+        if let Some(renderer) = &renderer {
+            renderer.wait_idle();
+        }
+
+        drop(self.reg);
+        drop(renderer);
+        drop(window);
+    }
+
+    /// Returns how the event loop should wait instead of updating, if the
+    /// window is minimized or unfocused and this update should be skipped.
+    /// Events are still handled in the meantime.
+    fn throttle(&mut self) -> Option<ControlFlow> {
+        if self.minimized {
+            return Some(ControlFlow::Wait);
+        }
+
+        if self.focused {
+            return None;
+        }
+
+        let rate = self.reg.res::<Settings>().unfocused_update_rate_hz;
+
+        if rate <= 0.0 {
+            return Some(ControlFlow::Wait);
+        }
+
+        let now = Instant::now();
+
+        if now < self.next_unfocused_update {
+            return Some(ControlFlow::WaitUntil(self.next_unfocused_update));
+        }
+
+        self.next_unfocused_update = now + Duration::from_secs_f32(1.0 / rate);
+
+        None
+    }
+
     fn update(&mut self) -> EventLoopIterationDecision {
-        (self.schedule)(&self.reg).execute(Stage::EachStep, &mut self.reg);
+        let profiling = self.reg.res_mut::<FrameProfiler>().begin_frame();
+        let mut system_timings = Vec::new();
+
+        if profiling {
+            self.reg.res_mut::<Renderer>().begin_profile();
+            self.schedule
+                .execute_profiled(Stage::EachStep, &mut self.reg, &mut system_timings);
+        } else {
+            self.schedule.execute(Stage::EachStep, &mut self.reg);
+        }
+
+        let pass_timings = self.reg.res_mut::<Renderer>().end_profile();
+        let profile = self
+            .reg
+            .res_mut::<FrameProfiler>()
+            .end_frame(system_timings, pass_timings);
+
+        if let Some(profile) = profile {
+            profile.save();
+        }
 
         self.reg.res_mut::<InputState>().reset_mouse_movement();
+        self.reg.res_mut::<InputState>().reset_just_pressed_keys();
 
         if self.reg.res::<EngineState>().quit {
             return EventLoopIterationDecision::Break;
@@ -150,14 +328,56 @@ impl AppState {
     }
 }
 
+/// Starts out with the scene at `path` as the current one. A scene that fails
+/// to load is replaced by an empty one.
+fn load_initial_scene(path: Option<PathBuf>) -> SceneGraph {
+    let mut sg = SceneGraph::new();
+
+    let Some(path) = path else {
+        return sg;
+    };
+
+    let scene = Scene::load(&path).unwrap_or_else(|err| {
+        error!(?path, %err, "failed to load initial scene");
+        Scene::new()
+    });
+
+    let id = sg.add_scene(scene);
+    sg.set_current_scene_id(id).unwrap();
+
+    sg
+}
+
 pub enum EventLoopIterationDecision {
     Continue,
     Break,
 }
 
+#[derive(Default)]
 pub struct AppInfo {
     pub internal_name: String,
     pub title: String,
+    /// Scene file to start with, overridden by `--scene <path>` on the
+    /// command line.
+    pub initial_scene: Option<PathBuf>,
+}
+
+impl AppInfo {
+    fn initial_scene(&self) -> Option<PathBuf> {
+        let mut args = std::env::args().skip(1);
+
+        while let Some(arg) = args.next() {
+            if arg == "--scene" {
+                return args.next().map(PathBuf::from);
+            }
+
+            if let Some(path) = arg.strip_prefix("--scene=") {
+                return Some(PathBuf::from(path));
+            }
+        }
+
+        self.initial_scene.clone()
+    }
 }
 
 pub struct App {
@@ -193,7 +413,11 @@ impl ApplicationHandler for App {
         let window = event_loop
             .create_window(Window::default_attributes().with_title(&self.info.title))
             .unwrap();
-        self.state = Some(AppState::new(window));
+        self.state = Some(AppState::new(
+            window,
+            self.info.initial_scene(),
+            &*self.schedule,
+        ));
     }
 
     fn window_event(
@@ -220,9 +444,25 @@ impl ApplicationHandler for App {
         }
     }
 
+    fn exiting(&mut self, event_loop: &ActiveEventLoop) {
+        if let Some(state) = self.state.take() {
+            state.shutdown();
+        }
+    }
+
     fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
-        let it = self.state.as_mut().map(|s| s.update());
-        if let Some(EventLoopIterationDecision::Break) = it {
+        let Some(state) = self.state.as_mut() else {
+            return;
+        };
+
+        if let Some(control_flow) = state.throttle() {
+            event_loop.set_control_flow(control_flow);
+            return;
+        }
+
+        event_loop.set_control_flow(ControlFlow::Poll);
+
+        if let EventLoopIterationDecision::Break = state.update() {
             event_loop.exit();
         }
     }
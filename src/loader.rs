@@ -1,24 +1,93 @@
 use std::sync::Arc;
 
-use crate::asset::{import_obj, AssetId, Vfs};
-use crate::asset::{Model, Shader, ShaderStage};
-use crate::core::ResMut;
+use crate::asset::{import_obj_streaming, AssetId, ImportOptions, ImportProgress, Vfs};
+use crate::asset::{Mesh, Shader, ShaderStage};
+use crate::core::{EventsMut, ResMut};
 use hassle_rs::{Dxc, DxcCompiler, DxcIncludeHandler, DxcLibrary, HassleError};
 use rayon::ThreadPool;
+use tracing::{error, info};
 
 use crossbeam_channel as channel;
 
 pub struct Loader {
     vfs: Arc<Vfs>,
     thread_pool: Arc<ThreadPool>,
+    import_status: ImportStatus,
 
-    model_tx: channel::Sender<LoadResponse<Model>>,
-    model_rx: channel::Receiver<LoadResponse<Model>>,
+    model_tx: channel::Sender<LoadResponse>,
+    model_rx: channel::Receiver<LoadResponse>,
 }
 
-enum LoadResponse<T> {
-    Done((AssetId, T)),
-    Error(Box<dyn std::error::Error + Send>),
+enum LoadResponse {
+    Mesh((AssetId, Mesh, ImportProgress)),
+    Done(AssetId),
+    Error((AssetId, Box<dyn std::error::Error + Send>)),
+}
+
+/// Emitted by [`poll`] for every mesh of a model as soon as it's imported,
+/// so uploading can start before the whole model is done.
+pub struct MeshLoaded {
+    pub model: AssetId,
+    pub mesh: Mesh,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ImportState {
+    Loading(ImportProgress),
+    Done,
+    Failed(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct ImportEntry {
+    pub id: AssetId,
+    pub path: String,
+    pub state: ImportState,
+}
+
+/// Results of asset imports. Entries stay around until they're dismissed.
+#[derive(Default)]
+pub struct ImportStatus {
+    entries: Vec<ImportEntry>,
+}
+
+impl ImportStatus {
+    pub fn entries(&self) -> impl Iterator<Item = &ImportEntry> {
+        self.entries.iter()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn dismiss(&mut self, id: AssetId) {
+        self.entries.retain(|entry| entry.id != id);
+    }
+
+    /// Dismisses all imports that are no longer loading.
+    pub fn dismiss_finished(&mut self) {
+        self.entries
+            .retain(|entry| matches!(entry.state, ImportState::Loading(_)));
+    }
+
+    fn begin(&mut self, id: AssetId, path: &str) {
+        let entry = ImportEntry {
+            id,
+            path: path.to_owned(),
+            state: ImportState::Loading(ImportProgress::default()),
+        };
+
+        match self.entries.iter_mut().find(|entry| entry.id == id) {
+            Some(existing) => *existing = entry,
+            None => self.entries.push(entry),
+        }
+    }
+
+    fn set_state(&mut self, id: AssetId, state: ImportState) {
+        if let Some(entry) = self.entries.iter_mut().find(|entry| entry.id == id) {
+            entry.state = state;
+        }
+    }
 }
 
 impl Loader {
@@ -28,6 +97,7 @@ impl Loader {
         Self {
             vfs,
             thread_pool,
+            import_status: ImportStatus::default(),
 
             model_tx,
             model_rx,
@@ -38,17 +108,42 @@ impl Loader {
         &self.vfs
     }
 
-    pub fn load_model_async(&self, path: &str) -> AssetId {
+    pub fn import_status(&self) -> &ImportStatus {
+        &self.import_status
+    }
+
+    pub fn import_status_mut(&mut self) -> &mut ImportStatus {
+        &mut self.import_status
+    }
+
+    pub fn load_model_async(&mut self, path: &str) -> AssetId {
+        self.load_model_with_options_async(path, ImportOptions::default())
+    }
+
+    /// Imports the model on the thread pool. Its meshes arrive as
+    /// [`MeshLoaded`] events as they're imported.
+    pub fn load_model_with_options_async(&mut self, path: &str, options: ImportOptions) -> AssetId {
         let id = self.vfs.acquire_asset_id_for_path(path);
 
+        self.import_status.begin(id, path);
+
         let path = path.to_owned();
 
         let model_tx = self.model_tx.clone();
 
         self.thread_pool.spawn(move || {
             let response = std::fs::read(path)
-                .map(|data| LoadResponse::Done((id, import_obj(&data))))
-                .unwrap_or_else(|err| LoadResponse::Error(Box::new(err)));
+                .map_err(|err| Box::new(err) as Box<dyn std::error::Error + Send>)
+                .and_then(|data| {
+                    import_obj_streaming(&data, &options, |mesh, progress| {
+                        model_tx
+                            .send(LoadResponse::Mesh((id, mesh, progress)))
+                            .unwrap();
+                    })
+                    .map_err(|err| Box::new(err) as _)
+                })
+                .map(|()| LoadResponse::Done(id))
+                .unwrap_or_else(|err| LoadResponse::Error((id, err)));
 
             model_tx.send(response).unwrap();
         });
@@ -57,14 +152,26 @@ impl Loader {
     }
 }
 
-pub fn poll(loader: ResMut<Loader>) {
+pub fn poll(mut loader: ResMut<Loader>, mut loaded: EventsMut<MeshLoaded>) {
+    let loader = &mut *loader;
+
     for load_response in loader.model_rx.try_iter() {
         match load_response {
-            LoadResponse::Done((id, model)) => {
-                println!("loaded: {:?}", id);
+            LoadResponse::Mesh((id, mesh, progress)) => {
+                loader
+                    .import_status
+                    .set_state(id, ImportState::Loading(progress));
+                loaded.emit(MeshLoaded { model: id, mesh });
             }
-            LoadResponse::Error(err) => {
-                println!("error: {}", err);
+            LoadResponse::Done(id) => {
+                info!(?id, "loaded model");
+                loader.import_status.set_state(id, ImportState::Done);
+            }
+            LoadResponse::Error((id, err)) => {
+                error!(?id, %err, "failed to load model");
+                loader
+                    .import_status
+                    .set_state(id, ImportState::Failed(err.to_string()));
             }
         }
     }
@@ -73,8 +180,13 @@ pub fn poll(loader: ResMut<Loader>) {
 #[derive(thiserror::Error, Debug)]
 #[non_exhaustive]
 pub enum Error {
-    #[error("HLSL error:\n{0}")]
-    Compile(String),
+    #[error("{path} ({stage:?}): HLSL error:\n{message}")]
+    Compile {
+        path: String,
+        stage: ShaderStage,
+        /// Diagnostics printed by the compiler.
+        message: String,
+    },
 
     #[error("shader compiler error: {0}")]
     Hassle(#[from] HassleError),
@@ -90,6 +202,14 @@ fn read_shader_source(path: &str) -> Result<String, Error> {
     Ok(std::fs::read_to_string(path)?)
 }
 
+/// Fallback for shaders that fail to compile, see
+/// [`ShaderCompiler::compile_hlsl_or_error`].
+pub const ERROR_SHADER_PATH: &str = "videoland/data/shaders/error.hlsl";
+
+// built in, so the fallback doesn't depend on the data directory
+const ERROR_SHADER_SOURCE: &str = include_str!("../data/shaders/error.hlsl");
+
+#[derive(Debug, Clone, Copy)]
 pub enum ShaderBytecode {
     SpirV,
     Dxil,
@@ -153,9 +273,19 @@ impl ShaderCompiler {
     ) -> Result<Shader, Error> {
         let source = read_shader_source(path)?;
 
+        self.compile_hlsl_source(path, &source, stage, bytecode)
+    }
+
+    fn compile_hlsl_source(
+        &self,
+        path: &str,
+        source: &str,
+        stage: ShaderStage,
+        bytecode: ShaderBytecode,
+    ) -> Result<Shader, Error> {
         let blob = self
             .library
-            .create_blob_with_encoding_from_str(&source)
+            .create_blob_with_encoding_from_str(source)
             .unwrap();
 
         let profile = shader_profile_name(stage);
@@ -187,8 +317,33 @@ impl ShaderCompiler {
                     .library
                     .get_blob_as_string(&err.0.get_error_buffer().unwrap().into())?;
 
-                Err(Error::Compile(message))
+                Err(Error::Compile {
+                    path: path.to_owned(),
+                    stage,
+                    message,
+                })
             }
         }
     }
+
+    /// Like [`ShaderCompiler::compile_hlsl`], but logs failures and returns
+    /// the same stage of [`ERROR_SHADER_PATH`] instead, which draws magenta.
+    pub fn compile_hlsl_or_error(
+        &self,
+        path: &str,
+        stage: ShaderStage,
+        bytecode: ShaderBytecode,
+    ) -> Shader {
+        self.compile_hlsl(path, stage, bytecode)
+            .unwrap_or_else(|err| {
+                error!("{err}");
+                self.compile_error_shader(stage, bytecode)
+            })
+    }
+
+    /// Compiles the built-in copy of [`ERROR_SHADER_PATH`].
+    pub fn compile_error_shader(&self, stage: ShaderStage, bytecode: ShaderBytecode) -> Shader {
+        self.compile_hlsl_source(ERROR_SHADER_PATH, ERROR_SHADER_SOURCE, stage, bytecode)
+            .expect("built-in error shader should compile")
+    }
 }
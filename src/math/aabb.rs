@@ -0,0 +1,45 @@
+use glam::{Mat4, Vec3};
+
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Aabb {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl Aabb {
+    pub fn new(min: Vec3, max: Vec3) -> Self {
+        Self { min, max }
+    }
+
+    pub fn center(&self) -> Vec3 {
+        (self.min + self.max) * 0.5
+    }
+
+    pub fn half_extents(&self) -> Vec3 {
+        (self.max - self.min) * 0.5
+    }
+
+    pub fn union(&self, other: &Aabb) -> Aabb {
+        Aabb {
+            min: self.min.min(other.min),
+            max: self.max.max(other.max),
+        }
+    }
+
+    /// Returns the box enclosing this one after it's transformed by `matrix`.
+    pub fn transformed(&self, matrix: Mat4) -> Aabb {
+        let center = matrix.transform_point3(self.center());
+        let half_extents = self.half_extents();
+
+        // each axis of the new box is the sum of the absolute projections of
+        // the old half extents onto it
+        let extents = matrix.x_axis.truncate().abs() * half_extents.x
+            + matrix.y_axis.truncate().abs() * half_extents.y
+            + matrix.z_axis.truncate().abs() * half_extents.z;
+
+        Aabb {
+            min: center - extents,
+            max: center + extents,
+        }
+    }
+}
@@ -0,0 +1,145 @@
+use glam::{Mat4, Vec3, Vec4};
+
+use crate::math::Aabb;
+
+// Shapes touching a plane are considered inside; this absorbs the rounding
+// error of plane extraction for points lying exactly on a plane.
+const EPSILON: f32 = 1e-5;
+
+/// View frustum as six planes with normals pointing inwards. Each plane is
+/// stored as `(normal, d)`, so that `normal.dot(p) + d` is the signed
+/// distance from `p` to the plane.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Frustum {
+    planes: [Vec4; 6],
+}
+
+impl Frustum {
+    /// Extracts frustum planes from a view-projection matrix with `0..1`
+    /// clip-space depth, like the ones produced by `Mat4::perspective_rh`.
+    pub fn from_view_projection(view_projection: Mat4) -> Self {
+        let row = |index| view_projection.row(index);
+
+        let planes = [
+            row(3) + row(0), // left
+            row(3) - row(0), // right
+            row(3) + row(1), // bottom
+            row(3) - row(1), // top
+            row(2),          // near
+            row(3) - row(2), // far
+        ];
+
+        Self {
+            planes: planes.map(|plane| plane / plane.truncate().length()),
+        }
+    }
+
+    pub fn planes(&self) -> &[Vec4; 6] {
+        &self.planes
+    }
+
+    pub fn contains_point(&self, point: Vec3) -> bool {
+        self.contains_sphere(point, 0.0)
+    }
+
+    /// Returns `false` only if the sphere is entirely outside the frustum.
+    pub fn contains_sphere(&self, center: Vec3, radius: f32) -> bool {
+        self.planes
+            .iter()
+            .all(|plane| signed_distance(*plane, center) >= -radius - EPSILON)
+    }
+
+    /// Returns `false` only if the box is entirely outside the frustum.
+    pub fn contains_aabb(&self, aabb: &Aabb) -> bool {
+        self.planes.iter().all(|plane| {
+            // corner of the box that is furthest along the plane normal
+            let normal = plane.truncate();
+            let corner = Vec3::select(normal.cmpge(Vec3::ZERO), aabb.max, aabb.min);
+
+            signed_distance(*plane, corner) >= -EPSILON
+        })
+    }
+}
+
+fn signed_distance(plane: Vec4, point: Vec3) -> f32 {
+    plane.truncate().dot(point) + plane.w
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::vec3;
+
+    use super::*;
+
+    // 90 degree FOV looking down -Z, so the side planes are x = ±z and y = ±z.
+    fn perspective() -> Frustum {
+        let projection = Mat4::perspective_rh(90f32.to_radians(), 1.0, 1.0, 10.0);
+        Frustum::from_view_projection(projection)
+    }
+
+    #[test]
+    fn planes_are_normalized() {
+        for plane in perspective().planes() {
+            assert!((plane.truncate().length() - 1.0).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn points() {
+        let frustum = perspective();
+
+        assert!(frustum.contains_point(vec3(0.0, 0.0, -5.0)));
+        assert!(!frustum.contains_point(vec3(0.0, 0.0, -0.5)));
+        assert!(!frustum.contains_point(vec3(0.0, 0.0, -10.5)));
+        assert!(!frustum.contains_point(vec3(5.5, 0.0, -5.0)));
+        assert!(!frustum.contains_point(vec3(0.0, -5.5, -5.0)));
+    }
+
+    #[test]
+    fn points_on_planes() {
+        let frustum = perspective();
+
+        assert!(frustum.contains_point(vec3(0.0, 0.0, -1.0)));
+        assert!(frustum.contains_point(vec3(0.0, 0.0, -10.0)));
+        assert!(frustum.contains_point(vec3(5.0, 0.0, -5.0)));
+        assert!(frustum.contains_point(vec3(-5.0, 0.0, -5.0)));
+        assert!(frustum.contains_point(vec3(0.0, 5.0, -5.0)));
+        assert!(frustum.contains_point(vec3(10.0, -10.0, -10.0)));
+    }
+
+    #[test]
+    fn spheres() {
+        let frustum = perspective();
+
+        assert!(!frustum.contains_sphere(Vec3::ZERO, 0.5));
+        assert!(frustum.contains_sphere(Vec3::ZERO, 1.0));
+        assert!(frustum.contains_sphere(vec3(0.0, 0.0, -12.0), 2.5));
+        assert!(!frustum.contains_sphere(vec3(0.0, 0.0, -12.0), 1.5));
+    }
+
+    #[test]
+    fn aabbs() {
+        let frustum = perspective();
+
+        let inside = Aabb::new(vec3(-1.0, -1.0, -6.0), vec3(1.0, 1.0, -4.0));
+        let straddling = Aabb::new(vec3(4.0, -1.0, -6.0), vec3(8.0, 1.0, -4.0));
+        let touching = Aabb::new(vec3(-1.0, -1.0, -20.0), vec3(1.0, 1.0, -10.0));
+        let behind = Aabb::new(vec3(-1.0, -1.0, 1.0), vec3(1.0, 1.0, 2.0));
+
+        assert!(frustum.contains_aabb(&inside));
+        assert!(frustum.contains_aabb(&straddling));
+        assert!(frustum.contains_aabb(&touching));
+        assert!(!frustum.contains_aabb(&behind));
+    }
+
+    #[test]
+    fn view_transform() {
+        let projection = Mat4::perspective_rh(90f32.to_radians(), 1.0, 1.0, 10.0);
+        let view = Mat4::look_at_rh(vec3(100.0, 0.0, 0.0), vec3(100.0, 0.0, 5.0), Vec3::Y);
+        let frustum = Frustum::from_view_projection(projection * view);
+
+        assert!(frustum.contains_point(vec3(100.0, 0.0, 5.0)));
+        assert!(!frustum.contains_point(vec3(100.0, 0.0, -5.0)));
+        assert!(!frustum.contains_point(vec3(0.0, 0.0, 5.0)));
+    }
+}
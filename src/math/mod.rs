@@ -0,0 +1,9 @@
+mod aabb;
+mod frustum;
+mod ray;
+
+pub use glam::*;
+
+pub use self::aabb::*;
+pub use self::frustum::*;
+pub use self::ray::*;
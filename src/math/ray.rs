@@ -0,0 +1,145 @@
+use glam::{Mat4, Vec2, Vec3};
+
+use crate::math::Aabb;
+
+/// Triangles closer than this to parallel with the ray are never hit.
+const EPSILON: f32 = 1e-7;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ray {
+    pub origin: Vec3,
+    /// Not necessarily normalized; hit distances are in multiples of it.
+    pub dir: Vec3,
+}
+
+impl Ray {
+    pub fn new(origin: Vec3, dir: Vec3) -> Self {
+        Self { origin, dir }
+    }
+
+    /// Ray from the near plane through `ndc`, with `y` pointing up, of a
+    /// camera with `0..1` clip-space depth. The direction is normalized.
+    pub fn from_ndc(ndc: Vec2, inverse_view_projection: Mat4) -> Self {
+        let near = inverse_view_projection.project_point3(ndc.extend(0.0));
+        let far = inverse_view_projection.project_point3(ndc.extend(1.0));
+
+        Self {
+            origin: near,
+            dir: (far - near).normalize(),
+        }
+    }
+
+    pub fn at(&self, t: f32) -> Vec3 {
+        self.origin + self.dir * t
+    }
+}
+
+/// Distance along `ray` to where it enters `aabb`, 0 if it starts inside.
+pub fn ray_aabb(ray: &Ray, aabb: &Aabb) -> Option<f32> {
+    // slab method, infinities from zero direction components work out
+    let inverse_dir = ray.dir.recip();
+
+    let t0 = (aabb.min - ray.origin) * inverse_dir;
+    let t1 = (aabb.max - ray.origin) * inverse_dir;
+
+    let t_enter = t0.min(t1).max_element();
+    let t_exit = t0.max(t1).min_element();
+
+    (t_enter <= t_exit && t_exit >= 0.0).then_some(t_enter.max(0.0))
+}
+
+/// Distance along `ray` to where it hits the triangle, from either side.
+/// Möller–Trumbore.
+pub fn ray_triangle(ray: &Ray, [a, b, c]: [Vec3; 3]) -> Option<f32> {
+    let edge1 = b - a;
+    let edge2 = c - a;
+
+    let p = ray.dir.cross(edge2);
+    let det = edge1.dot(p);
+
+    if det.abs() < EPSILON {
+        return None;
+    }
+
+    let inverse_det = det.recip();
+    let s = ray.origin - a;
+
+    let u = s.dot(p) * inverse_det;
+    if !(0.0..=1.0).contains(&u) {
+        return None;
+    }
+
+    let q = s.cross(edge1);
+
+    let v = ray.dir.dot(q) * inverse_det;
+    if v < 0.0 || u + v > 1.0 {
+        return None;
+    }
+
+    let t = edge2.dot(q) * inverse_det;
+
+    (t >= 0.0).then_some(t)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unit_box() -> Aabb {
+        Aabb::new(Vec3::splat(-1.0), Vec3::splat(1.0))
+    }
+
+    #[test]
+    fn ray_hits_box() {
+        let ray = Ray::new(Vec3::new(0.5, 0.5, 5.0), Vec3::NEG_Z);
+        assert_eq!(ray_aabb(&ray, &unit_box()), Some(4.0));
+
+        let inside = Ray::new(Vec3::ZERO, Vec3::X);
+        assert_eq!(ray_aabb(&inside, &unit_box()), Some(0.0));
+    }
+
+    #[test]
+    fn ray_misses_box() {
+        let past = Ray::new(Vec3::new(2.0, 0.0, 5.0), Vec3::NEG_Z);
+        assert_eq!(ray_aabb(&past, &unit_box()), None);
+
+        let behind = Ray::new(Vec3::new(0.0, 0.0, 5.0), Vec3::Z);
+        assert_eq!(ray_aabb(&behind, &unit_box()), None);
+    }
+
+    #[test]
+    fn ray_hits_triangle() {
+        let triangle = [Vec3::ZERO, Vec3::X, Vec3::Y];
+
+        let ray = Ray::new(Vec3::new(0.25, 0.25, 2.0), Vec3::NEG_Z);
+        assert_eq!(ray_triangle(&ray, triangle), Some(2.0));
+
+        let back = Ray::new(Vec3::new(0.25, 0.25, -3.0), Vec3::Z);
+        assert_eq!(ray_triangle(&back, triangle), Some(3.0));
+    }
+
+    #[test]
+    fn ray_misses_triangle() {
+        let triangle = [Vec3::ZERO, Vec3::X, Vec3::Y];
+
+        let outside = Ray::new(Vec3::new(0.75, 0.75, 2.0), Vec3::NEG_Z);
+        assert_eq!(ray_triangle(&outside, triangle), None);
+
+        let parallel = Ray::new(Vec3::new(-1.0, 0.25, 0.0), Vec3::X);
+        assert_eq!(ray_triangle(&parallel, triangle), None);
+
+        let behind = Ray::new(Vec3::new(0.25, 0.25, 2.0), Vec3::Z);
+        assert_eq!(ray_triangle(&behind, triangle), None);
+    }
+
+    #[test]
+    fn ray_from_ndc_center_follows_view() {
+        let view = Mat4::look_at_rh(Vec3::new(0.0, 0.0, 5.0), Vec3::ZERO, Vec3::Y);
+        let projection = Mat4::perspective_rh(1.0, 1.0, 0.1, 100.0);
+
+        let ray = Ray::from_ndc(Vec2::ZERO, (projection * view).inverse());
+
+        assert!(ray.dir.abs_diff_eq(Vec3::NEG_Z, 1e-4));
+        assert!((ray.origin.z - 4.9).abs() < 1e-3);
+    }
+}
@@ -0,0 +1,157 @@
+use std::fmt;
+use std::time::{Duration, Instant, SystemTime};
+
+use tracing::{error, info};
+
+use crate::core::SystemTiming;
+use crate::render::PassTiming;
+
+/// Records a detailed profile of a single frame on request. Nothing is
+/// measured for frames that aren't captured.
+pub struct FrameProfiler {
+    requested: bool,
+    capturing: bool,
+    end_of_previous_frame: Instant,
+}
+
+impl FrameProfiler {
+    pub fn new() -> Self {
+        Self {
+            requested: false,
+            capturing: false,
+            end_of_previous_frame: Instant::now(),
+        }
+    }
+
+    /// Profiles the next frame from start to finish.
+    pub fn request(&mut self) {
+        self.requested = true;
+    }
+
+    pub fn is_capturing(&self) -> bool {
+        self.capturing
+    }
+
+    /// Called before the frame's systems run. Returns whether this frame is
+    /// being captured.
+    pub(crate) fn begin_frame(&mut self) -> bool {
+        self.capturing = std::mem::take(&mut self.requested);
+        self.capturing
+    }
+
+    /// Called once the frame is presented. Returns the profile if this frame
+    /// was captured.
+    pub(crate) fn end_frame(
+        &mut self,
+        systems: Vec<SystemTiming>,
+        passes: Vec<PassTiming>,
+    ) -> Option<FrameProfile> {
+        let now = Instant::now();
+        let frame_start = std::mem::replace(&mut self.end_of_previous_frame, now);
+
+        if !std::mem::take(&mut self.capturing) {
+            return None;
+        }
+
+        let systems_time = systems.iter().map(|system| system.duration).sum();
+        let total = now - frame_start;
+
+        Some(FrameProfile {
+            events: total.saturating_sub(systems_time),
+            systems,
+            passes,
+            total,
+        })
+    }
+}
+
+pub struct FrameProfile {
+    /// Time between the end of the previous frame and the first system,
+    /// spent handling window and device input.
+    pub events: Duration,
+    pub systems: Vec<SystemTiming>,
+    /// Render passes, which run inside systems.
+    pub passes: Vec<PassTiming>,
+    pub total: Duration,
+}
+
+impl FrameProfile {
+    pub fn draw_calls(&self) -> u32 {
+        self.passes.iter().map(|pass| pass.draw_calls).sum()
+    }
+
+    /// Writes the profile to `frame-profile-<timestamp>.txt` in the working
+    /// directory.
+    pub fn save(&self) {
+        let timestamp = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis();
+        let path = format!("frame-profile-{timestamp}.txt");
+
+        match std::fs::write(&path, self.to_string()) {
+            Ok(()) => info!(
+                path,
+                total_ms = self.total.as_secs_f64() * 1000.0,
+                draw_calls = self.draw_calls(),
+                "saved frame profile"
+            ),
+            Err(err) => error!(path, %err, "failed to save frame profile"),
+        }
+    }
+}
+
+impl fmt::Display for FrameProfile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let ms = |duration: Duration| duration.as_secs_f64() * 1000.0;
+
+        writeln!(f, "frame: {:.3} ms", ms(self.total))?;
+        writeln!(f, "events: {:.3} ms", ms(self.events))?;
+
+        writeln!(f, "\nsystems:")?;
+        for system in &self.systems {
+            writeln!(f, "  {:>9.3} ms  {}", ms(system.duration), system.name)?;
+        }
+
+        writeln!(f, "\npasses:")?;
+        for pass in &self.passes {
+            writeln!(
+                f,
+                "  {:>9.3} ms  {:>5} draws  {}",
+                ms(pass.duration),
+                pass.draw_calls,
+                pass.name
+            )?;
+        }
+
+        writeln!(f, "\ndraw calls: {}", self.draw_calls())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_requested_frame_is_captured() {
+        let mut profiler = FrameProfiler::new();
+
+        assert!(!profiler.begin_frame());
+        assert!(profiler.end_frame(Vec::new(), Vec::new()).is_none());
+
+        profiler.request();
+
+        assert!(profiler.begin_frame());
+        let profile = profiler.end_frame(
+            Vec::new(),
+            vec![PassTiming {
+                name: "scene",
+                duration: Duration::ZERO,
+                draw_calls: 2,
+            }],
+        );
+        assert_eq!(profile.unwrap().draw_calls(), 2);
+
+        assert!(!profiler.begin_frame());
+    }
+}
@@ -0,0 +1,75 @@
+use std::ops::Range;
+
+use crate::render::{MaterialHandle, MeshHandle};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct DrawCommand {
+    pub material: MaterialHandle,
+    pub mesh: MeshHandle,
+    pub object_index: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct MaterialBatch {
+    pub material: MaterialHandle,
+    pub draws: Range<usize>,
+}
+
+/// Draws keep their relative order within a batch.
+pub(super) fn batch_by_material(draws: &mut [DrawCommand]) -> Vec<MaterialBatch> {
+    draws.sort_by_key(|draw| draw.material);
+
+    let mut batches = Vec::new();
+    let mut start = 0;
+
+    for chunk in draws.chunk_by(|a, b| a.material == b.material) {
+        batches.push(MaterialBatch {
+            material: chunk[0].material,
+            draws: start..start + chunk.len(),
+        });
+        start += chunk.len();
+    }
+
+    batches
+}
+
+#[cfg(test)]
+mod tests {
+    use uuid::Uuid;
+
+    use super::*;
+
+    #[test]
+    fn draws_are_grouped_by_material() {
+        let [a, b] = [1, 2].map(|i| MaterialHandle(Uuid::from_u128(i)));
+
+        let mut draws: Vec<DrawCommand> = [b, a, b, a]
+            .into_iter()
+            .enumerate()
+            .map(|(i, material)| DrawCommand {
+                material,
+                mesh: MeshHandle(Uuid::nil()),
+                object_index: i as u32,
+            })
+            .collect();
+
+        let batches = batch_by_material(&mut draws);
+
+        assert_eq!(
+            batches,
+            [
+                MaterialBatch {
+                    material: a,
+                    draws: 0..2
+                },
+                MaterialBatch {
+                    material: b,
+                    draws: 2..4
+                },
+            ]
+        );
+
+        let object_indices: Vec<u32> = draws.iter().map(|draw| draw.object_index).collect();
+        assert_eq!(object_indices, [1, 3, 0, 2]);
+    }
+}
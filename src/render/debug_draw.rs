@@ -0,0 +1,148 @@
+use glam::Vec3;
+
+use crate::color::Color;
+
+#[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+#[repr(C)]
+pub(super) struct DebugVertex {
+    position: [f32; 3],
+    color: [f32; 4],
+}
+
+impl DebugVertex {
+    pub(super) fn layout() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            attributes: &[
+                wgpu::VertexAttribute {
+                    format: wgpu::VertexFormat::Float32x3,
+                    offset: 0,
+                    shader_location: 0,
+                },
+                wgpu::VertexAttribute {
+                    format: wgpu::VertexFormat::Float32x4,
+                    offset: 3 * 4,
+                    shader_location: 1,
+                },
+            ],
+            array_stride: 7 * 4,
+            step_mode: wgpu::VertexStepMode::Vertex,
+        }
+    }
+}
+
+const SPHERE_SEGMENTS: usize = 32;
+
+/// Line segments drawn on top of the scene for one frame. Any system can add
+/// lines, they're uploaded and cleared by [`crate::sys::upload_debug_draw`].
+#[derive(Default)]
+pub struct DebugDraw {
+    vertices: Vec<DebugVertex>,
+    depth_tested_vertices: Vec<DebugVertex>,
+    depth_test: bool,
+}
+
+impl DebugDraw {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Lines drawn by `draw` are hidden behind scene geometry instead of
+    /// being drawn over it, e.g. for a ground grid.
+    pub fn with_depth_test(&mut self, draw: impl FnOnce(&mut Self)) {
+        let previous = std::mem::replace(&mut self.depth_test, true);
+        draw(self);
+        self.depth_test = previous;
+    }
+
+    pub fn line(&mut self, a: Vec3, b: Vec3, color: Color) {
+        let color = color.to_array();
+
+        let vertices = if self.depth_test {
+            &mut self.depth_tested_vertices
+        } else {
+            &mut self.vertices
+        };
+
+        vertices.push(DebugVertex {
+            position: a.to_array(),
+            color,
+        });
+        vertices.push(DebugVertex {
+            position: b.to_array(),
+            color,
+        });
+    }
+
+    pub fn aabb(&mut self, min: Vec3, max: Vec3, color: Color) {
+        let corner = |i: usize| {
+            Vec3::new(
+                if i & 1 == 0 { min.x } else { max.x },
+                if i & 2 == 0 { min.y } else { max.y },
+                if i & 4 == 0 { min.z } else { max.z },
+            )
+        };
+
+        // each edge connects corners that differ in exactly one axis
+        for i in 0..8 {
+            for axis in [1, 2, 4] {
+                if i & axis == 0 {
+                    self.line(corner(i), corner(i | axis), color);
+                }
+            }
+        }
+    }
+
+    /// Draws a sphere as three circles around the X, Y and Z axes.
+    pub fn sphere(&mut self, center: Vec3, radius: f32, color: Color) {
+        for (u, v) in [(Vec3::Y, Vec3::Z), (Vec3::X, Vec3::Z), (Vec3::X, Vec3::Y)] {
+            let point = |i: usize| {
+                let angle = i as f32 / SPHERE_SEGMENTS as f32 * std::f32::consts::TAU;
+                center + radius * (angle.cos() * u + angle.sin() * v)
+            };
+
+            for i in 0..SPHERE_SEGMENTS {
+                self.line(point(i), point(i + 1), color);
+            }
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.vertices.is_empty() && self.depth_tested_vertices.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.vertices.clear();
+        self.depth_tested_vertices.clear();
+    }
+
+    pub(super) fn vertices(&self) -> &[DebugVertex] {
+        &self.vertices
+    }
+
+    pub(super) fn depth_tested_vertices(&self) -> &[DebugVertex] {
+        &self.depth_tested_vertices
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn depth_tested_lines_are_kept_apart() {
+        let mut debug_draw = DebugDraw::new();
+
+        debug_draw.line(Vec3::ZERO, Vec3::X, Color::WHITE);
+        debug_draw.with_depth_test(|debug_draw| {
+            debug_draw.line(Vec3::ZERO, Vec3::Y, Color::WHITE);
+            debug_draw.line(Vec3::ZERO, Vec3::Z, Color::WHITE);
+        });
+        debug_draw.line(Vec3::ZERO, Vec3::X, Color::WHITE);
+
+        assert_eq!(debug_draw.vertices().len(), 4);
+        assert_eq!(debug_draw.depth_tested_vertices().len(), 4);
+
+        debug_draw.clear();
+        assert!(debug_draw.is_empty());
+    }
+}
@@ -0,0 +1,260 @@
+use std::ops::Range;
+
+use crate::asset::Vertex;
+
+const PAGE_VERTICES: u32 = 1 << 18;
+const PAGE_INDICES: u32 = 1 << 20;
+const INDEX_SIZE: u64 = size_of::<u32>() as u64;
+
+fn vertex_size() -> u64 {
+    Vertex::layout().array_stride
+}
+
+#[derive(Debug, Clone)]
+pub(super) struct MeshAllocation {
+    pub page: usize,
+    pub vertices: Range<u32>,
+    pub indices: Range<u32>,
+}
+
+impl MeshAllocation {
+    pub fn base_vertex(&self) -> i32 {
+        self.vertices.start as i32
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MeshArenaStats {
+    pub pages: u32,
+    pub vertex_bytes_used: u64,
+    pub vertex_bytes_free: u64,
+    pub index_bytes_used: u64,
+    pub index_bytes_free: u64,
+}
+
+impl MeshArenaStats {
+    pub fn bytes_used(&self) -> u64 {
+        self.vertex_bytes_used + self.index_bytes_used
+    }
+
+    pub fn bytes_total(&self) -> u64 {
+        self.bytes_used() + self.vertex_bytes_free + self.index_bytes_free
+    }
+}
+
+struct Page {
+    vertex_buffer: wgpu::Buffer,
+    index_buffer: wgpu::Buffer,
+    vertices: RangeAllocator,
+    indices: RangeAllocator,
+}
+
+pub(super) struct MeshArena {
+    pages: Vec<Page>,
+}
+
+impl MeshArena {
+    pub fn new() -> Self {
+        Self { pages: Vec::new() }
+    }
+
+    pub fn upload(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        vertex_data: &[u8],
+        indices: &[u32],
+    ) -> MeshAllocation {
+        let vertex_count = (vertex_data.len() as u64 / vertex_size()) as u32;
+        let index_count = indices.len() as u32;
+
+        let allocation = self.allocate(vertex_count, index_count).unwrap_or_else(|| {
+            self.pages.push(Page::new(
+                device,
+                vertex_count.max(PAGE_VERTICES),
+                index_count.max(PAGE_INDICES),
+            ));
+
+            self.allocate(vertex_count, index_count).unwrap()
+        });
+
+        let page = &self.pages[allocation.page];
+
+        if !vertex_data.is_empty() {
+            queue.write_buffer(
+                &page.vertex_buffer,
+                allocation.vertices.start as u64 * vertex_size(),
+                vertex_data,
+            );
+        }
+
+        if !indices.is_empty() {
+            queue.write_buffer(
+                &page.index_buffer,
+                allocation.indices.start as u64 * INDEX_SIZE,
+                bytemuck::cast_slice(indices),
+            );
+        }
+
+        allocation
+    }
+
+    fn allocate(&mut self, vertex_count: u32, index_count: u32) -> Option<MeshAllocation> {
+        self.pages.iter_mut().enumerate().find_map(|(index, page)| {
+            let vertices = page.vertices.allocate(vertex_count)?;
+
+            let Some(indices) = page.indices.allocate(index_count) else {
+                page.vertices.free(vertices);
+                return None;
+            };
+
+            Some(MeshAllocation {
+                page: index,
+                vertices,
+                indices,
+            })
+        })
+    }
+
+    /// Writes to reused ranges are queued after frames that were already
+    /// submitted, so this is fine to call at any point.
+    pub fn free(&mut self, allocation: &MeshAllocation) {
+        let page = &mut self.pages[allocation.page];
+        page.vertices.free(allocation.vertices.clone());
+        page.indices.free(allocation.indices.clone());
+    }
+
+    pub fn buffers(&self, page: usize) -> (&wgpu::Buffer, &wgpu::Buffer) {
+        let page = &self.pages[page];
+        (&page.vertex_buffer, &page.index_buffer)
+    }
+
+    pub fn stats(&self) -> MeshArenaStats {
+        let mut stats = MeshArenaStats {
+            pages: self.pages.len() as u32,
+            ..Default::default()
+        };
+
+        for page in &self.pages {
+            stats.vertex_bytes_used += page.vertices.used() as u64 * vertex_size();
+            stats.vertex_bytes_free += page.vertices.free_len() as u64 * vertex_size();
+            stats.index_bytes_used += page.indices.used() as u64 * INDEX_SIZE;
+            stats.index_bytes_free += page.indices.free_len() as u64 * INDEX_SIZE;
+        }
+
+        stats
+    }
+}
+
+impl Page {
+    fn new(device: &wgpu::Device, vertex_capacity: u32, index_capacity: u32) -> Self {
+        let vertex_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("mesh arena vertices"),
+            size: vertex_capacity as u64 * vertex_size(),
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let index_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("mesh arena indices"),
+            size: index_capacity as u64 * INDEX_SIZE,
+            usage: wgpu::BufferUsages::INDEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        Self {
+            vertex_buffer,
+            index_buffer,
+            vertices: RangeAllocator::new(vertex_capacity),
+            indices: RangeAllocator::new(index_capacity),
+        }
+    }
+}
+
+struct RangeAllocator {
+    size: u32,
+    /// Sorted, with no two ranges touching.
+    free: Vec<Range<u32>>,
+}
+
+impl RangeAllocator {
+    fn new(size: u32) -> Self {
+        Self {
+            size,
+            free: vec![Range {
+                start: 0,
+                end: size,
+            }],
+        }
+    }
+
+    fn allocate(&mut self, len: u32) -> Option<Range<u32>> {
+        if len == 0 {
+            return Some(0..0);
+        }
+
+        let index = self
+            .free
+            .iter()
+            .position(|range| range.len() >= len as usize)?;
+        let range = &mut self.free[index];
+
+        let start = range.start;
+        range.start += len;
+
+        if range.start == range.end {
+            self.free.remove(index);
+        }
+
+        Some(start..start + len)
+    }
+
+    fn free(&mut self, range: Range<u32>) {
+        if range.is_empty() {
+            return;
+        }
+
+        let index = self.free.partition_point(|free| free.start < range.start);
+        self.free.insert(index, range);
+
+        if index + 1 < self.free.len() && self.free[index].end == self.free[index + 1].start {
+            self.free[index].end = self.free.remove(index + 1).end;
+        }
+
+        if index > 0 && self.free[index - 1].end == self.free[index].start {
+            self.free[index - 1].end = self.free.remove(index).end;
+        }
+    }
+
+    fn free_len(&self) -> u32 {
+        self.free.iter().map(|range| range.len() as u32).sum()
+    }
+
+    fn used(&self) -> u32 {
+        self.size - self.free_len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn freed_ranges_are_merged_and_reused() {
+        let mut allocator = RangeAllocator::new(100);
+
+        let a = allocator.allocate(30).unwrap();
+        let b = allocator.allocate(30).unwrap();
+        let c = allocator.allocate(30).unwrap();
+
+        assert_eq!([a.clone(), b.clone(), c], [0..30, 30..60, 60..90]);
+        assert_eq!(allocator.allocate(20), None);
+
+        allocator.free(a);
+        allocator.free(b);
+
+        assert_eq!(allocator.free, [0..60, 90..100]);
+        assert_eq!(allocator.allocate(50), Some(0..50));
+        assert_eq!(allocator.used(), 80);
+    }
+}
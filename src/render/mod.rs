@@ -1,17 +1,34 @@
 use std::borrow::Cow;
+use std::time::{Duration, Instant};
 
-use crate::asset::{Mesh, Model, Shader};
-use crate::scene::Scene;
+mod batch;
+mod debug_draw;
+mod mesh_arena;
+mod object;
+mod sampler;
+mod spirv;
+
+pub use self::debug_draw::*;
+pub use self::mesh_arena::MeshArenaStats;
+pub use self::sampler::*;
+
+use self::batch::{batch_by_material, DrawCommand, MaterialBatch};
+use self::mesh_arena::{MeshAllocation, MeshArena};
+use self::object::{ObjectBuffer, ObjectUniforms};
+use self::spirv::VertexInputError;
+
+use crate::asset::{Mesh, Model, Shader, ShaderStage};
+use crate::color::{linear_to_srgb, Color};
+use crate::scene::{Camera, Node, NodeHandle, Scene};
 use ahash::AHashMap;
-use glam::{Mat4, Vec2};
+use glam::{Mat4, Vec2, Vec4};
 use pollster::FutureExt;
 use raw_window_handle::{HasDisplayHandle, HasWindowHandle};
-use tracing::info;
+use tracing::{info, warn};
 use uuid::Uuid;
-use wgpu::util::DeviceExt;
 use winit::window::Window;
 
-#[derive(Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Extent2D {
     pub width: u32,
     pub height: u32,
@@ -26,66 +43,299 @@ impl From<Extent2D> for Vec2 {
     }
 }
 
+impl From<Color> for wgpu::Color {
+    fn from(value: Color) -> Self {
+        Self {
+            r: value.r as f64,
+            g: value.g as f64,
+            b: value.b as f64,
+            a: value.a as f64,
+        }
+    }
+}
+
 impl Extent2D {
+    pub fn is_empty(&self) -> bool {
+        self.width == 0 || self.height == 0
+    }
+
     pub fn aspect_ratio(&self) -> f32 {
+        if self.is_empty() {
+            return 1.0;
+        }
+
         self.width as f32 / self.height as f32
     }
 }
 
-#[derive(Default)]
 pub struct PreparedUi {
     pub shapes: Vec<egui::ClippedPrimitive>,
     pub textures_delta: egui::TexturesDelta,
+    pub pixels_per_point: f32,
+}
+
+impl Default for PreparedUi {
+    fn default() -> Self {
+        Self {
+            shapes: Vec::new(),
+            textures_delta: Default::default(),
+            pixels_per_point: 1.0,
+        }
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+#[non_exhaustive]
+pub enum RendererError {
+    #[error("{label}: invalid {stage:?} shader: {source}")]
+    Shader {
+        label: String,
+        stage: ShaderStage,
+        source: wgpu::Error,
+    },
+
+    #[error("{label}: failed to create pipeline: {source}")]
+    Pipeline { label: String, source: wgpu::Error },
+
+    #[error("{label}: vertex shader reads location {location}, which isn't in the vertex layout")]
+    MissingVertexAttribute { label: String, location: u32 },
+
+    #[error("{label}: vertex shader reads location {location} as {shader}, but the vertex layout has {layout:?}")]
+    VertexFormatMismatch {
+        label: String,
+        location: u32,
+        shader: String,
+        layout: wgpu::VertexFormat,
+    },
+
+    #[error("{label}: binding {binding} refers to a texture or sampler that isn't loaded")]
+    MissingBinding { label: String, binding: u32 },
+
+    #[error("headless renderer has no window to render to")]
+    Headless,
 }
 
+/// Material shaders get [`FrameUniforms`] at group 0, their own resources at
+/// group 1 and per-object data at group 2.
 #[derive(Clone)]
 pub struct MaterialDesc<'a> {
+    pub label: &'a str,
     pub vertex_shader: &'a Shader,
     pub fragment_shader: &'a Shader,
+    pub topology: wgpu::PrimitiveTopology,
+    /// Group 1, each at the binding of its index.
+    pub bindings: &'a [MaterialBinding],
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MaterialBinding {
+    Texture(TextureHandle),
+    Sampler(SamplerHandle),
 }
 
 struct GpuMaterial {
+    label: String,
+    bindings: Vec<MaterialBinding>,
     bind_group_layout: wgpu::BindGroupLayout,
+    bind_group: wgpu::BindGroup,
     pipeline_layout: wgpu::PipelineLayout,
     pipeline: wgpu::RenderPipeline,
+    depth_pipeline: wgpu::RenderPipeline,
+    depth_equal_pipeline: wgpu::RenderPipeline,
+    ref_count: u32,
+}
+
+pub const MAX_FRAMES_IN_FLIGHT: u32 = 3;
+
+const SCENE_COLOR_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+const OBJECT_ID_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::R32Uint;
+const DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
+
+struct RenderTarget {
+    color_view: wgpu::TextureView,
+    object_id: wgpu::Texture,
+    object_id_view: wgpu::TextureView,
+    depth_view: wgpu::TextureView,
+    size: Extent2D,
+}
+
+struct GpuTexture {
+    texture: wgpu::Texture,
+    view: wgpu::TextureView,
+    view_dimension: wgpu::TextureViewDimension,
+}
+
+pub struct ViewDesc<'a> {
+    pub camera: &'a Camera,
+    pub root: NodeHandle,
+    /// Top left corner, in fractions of the window size.
+    pub origin: Vec2,
+    /// In fractions of the window size.
+    pub size: Vec2,
+}
+
+impl ViewDesc<'_> {
+    /// Views sharing an edge round it the same way, so they neither overlap nor
+    /// leave a gap.
+    fn pixel_rect(&self, target: Extent2D) -> ViewRect {
+        let target = Vec2::new(target.width as f32, target.height as f32);
+
+        let min = (self.origin * target).round().max(Vec2::ZERO).min(target);
+        let max = ((self.origin + self.size) * target)
+            .round()
+            .max(min)
+            .min(target);
+
+        ViewRect {
+            origin: [min.x as u32, min.y as u32],
+            size: Extent2D {
+                width: (max.x - min.x) as u32,
+                height: (max.y - min.y) as u32,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct ViewRect {
+    origin: [u32; 2],
+    size: Extent2D,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pick {
+    /// [`NodeHandle::index`] of the node drawn there.
+    Object(u32),
+    Background,
+}
+
+#[derive(Debug, Clone)]
+pub struct PassTiming {
+    pub name: &'static str,
+    pub duration: Duration,
+    pub draw_calls: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorSpace {
+    Srgb,
+    Linear,
+}
+
+#[derive(Debug, Clone)]
+pub struct AdapterInfo {
+    pub name: String,
+    pub vendor_id: u32,
+    pub device_id: u32,
+    pub device_type: wgpu::DeviceType,
+    pub backend: wgpu::Backend,
+    pub driver: String,
+    pub driver_info: String,
+    pub max_texture_dimension: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct MaterialHandle(Uuid);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MeshHandle(Uuid);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TextureHandle(Uuid);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SamplerHandle(Uuid);
+
+struct GpuSampler {
+    desc: SamplerDesc,
+    sampler: wgpu::Sampler,
 }
 
 struct GpuMesh {
-    vertex_count: u32,
-    buffer: wgpu::Buffer,
+    allocation: MeshAllocation,
+    ref_count: u32,
+}
+
+#[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+#[repr(C)]
+struct FrameUniforms {
+    view_projection: Mat4,
+    inverse_view_projection: Mat4,
+    camera_position: Vec4,
+    /// Ambient color premultiplied by intensity, exposure in `w`.
+    ambient: Vec4,
 }
 
 #[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
 #[repr(C)]
 struct PushConstants {
-    camera_transform: Mat4,
     transform: Mat4,
 }
 
 pub struct Renderer {
     instance: wgpu::Instance,
-    surface: wgpu::Surface<'static>,
+    /// `None` for [`Renderer::headless`].
+    surface: Option<wgpu::Surface<'static>>,
     device: wgpu::Device,
     queue: wgpu::Queue,
+    adapter_info: AdapterInfo,
     surface_format: wgpu::TextureFormat,
+    ui_format: wgpu::TextureFormat,
+    surface_usage: wgpu::TextureUsages,
+    surface_size: Extent2D,
+    frames_in_flight: u32,
+    frame_index: u32,
+    supported_present_modes: Vec<wgpu::PresentMode>,
+    supported_alpha_modes: Vec<wgpu::CompositeAlphaMode>,
+    present_mode: wgpu::PresentMode,
+    max_supported_anisotropy: u16,
+    anisotropy: u16,
+    clear_color: Option<Color>,
+    depth_prepass: bool,
+
+    capture_requested: bool,
+    capture: Option<image::RgbaImage>,
+
+    pass_timings: Option<Vec<PassTiming>>,
+    frame_draw_calls: u32,
+    previous_frame_draw_calls: u32,
+
+    frame_bind_group_layout: wgpu::BindGroupLayout,
+    frame_uniforms: wgpu::Buffer,
+    frame_bind_group: wgpu::BindGroup,
+    objects: ObjectBuffer,
 
-    materials: AHashMap<Uuid, GpuMaterial>,
-    meshes: AHashMap<Uuid, GpuMesh>,
+    environment_bind_group_layout: wgpu::BindGroupLayout,
+    environment_sampler: wgpu::Sampler,
+    environment_bind_groups: AHashMap<TextureHandle, wgpu::BindGroup>,
+    skybox_pipeline: Option<wgpu::RenderPipeline>,
+
+    debug_pipeline: Option<wgpu::RenderPipeline>,
+    debug_depth_tested_pipeline: Option<wgpu::RenderPipeline>,
+    debug_vertex_buffer: Option<wgpu::Buffer>,
+    debug_vertex_count: u32,
+    debug_depth_tested_vertex_count: u32,
+
+    views_pipeline: Option<(wgpu::BindGroupLayout, wgpu::RenderPipeline)>,
+    views_target: Option<(RenderTarget, wgpu::BindGroup)>,
+    views_pending: bool,
+
+    materials: AHashMap<MaterialHandle, GpuMaterial>,
+    error_material: MaterialHandle,
+    meshes: AHashMap<MeshHandle, GpuMesh>,
+    mesh_arena: MeshArena,
+    textures: AHashMap<TextureHandle, GpuTexture>,
+    samplers: AHashMap<SamplerHandle, GpuSampler>,
 
     egui_renderer: egui_wgpu::Renderer,
-    egui_render_targets: AHashMap<egui::TextureId, (wgpu::TextureView, Extent2D)>,
+    egui_render_targets: AHashMap<egui::TextureId, RenderTarget>,
 }
 
 impl Renderer {
-    pub fn new(window: &Window, egui_vs: Shader, egui_fs: Shader) -> Self {
+    pub fn new(window: &Window, error_vs: Shader, error_fs: Shader) -> Self {
         let size = window.inner_size();
 
-        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
-            backends: wgpu::Backends::VULKAN,
-            flags: wgpu::InstanceFlags::empty(),
-            dx12_shader_compiler: wgpu::Dx12Compiler::Fxc,
-            gles_minor_version: wgpu::Gles3MinorVersion::Automatic,
-        });
+        let instance = create_instance();
 
         let raw_window_handle = window.window_handle().unwrap().as_raw();
         let raw_display_handle = window.display_handle().unwrap().as_raw();
@@ -98,11 +348,41 @@ impl Renderer {
         }
         .unwrap();
 
+        let size = Extent2D {
+            width: size.width,
+            height: size.height,
+        };
+
+        Self::create(instance, Some(surface), size, error_vs, error_fs)
+    }
+
+    /// Scenes can still be rendered into egui render targets and read back, but
+    /// [`Renderer::render`] returns [`RendererError::Headless`].
+    pub fn headless(error_vs: Shader, error_fs: Shader) -> Self {
+        Self::create(
+            create_instance(),
+            None,
+            Extent2D {
+                width: 0,
+                height: 0,
+            },
+            error_vs,
+            error_fs,
+        )
+    }
+
+    fn create(
+        instance: wgpu::Instance,
+        surface: Option<wgpu::Surface<'static>>,
+        surface_size: Extent2D,
+        error_vs: Shader,
+        error_fs: Shader,
+    ) -> Self {
         let adapter = instance
             .request_adapter(&wgpu::RequestAdapterOptions {
                 power_preference: wgpu::PowerPreference::HighPerformance,
                 force_fallback_adapter: false,
-                compatible_surface: Some(&surface),
+                compatible_surface: surface.as_ref(),
             })
             .block_on()
             .unwrap();
@@ -120,298 +400,1785 @@ impl Renderer {
             .block_on()
             .unwrap();
 
-        let surface_format = surface.get_capabilities(&adapter).formats[0];
+        let adapter_info = {
+            let info = adapter.get_info();
+
+            AdapterInfo {
+                name: info.name,
+                vendor_id: info.vendor,
+                device_id: info.device,
+                device_type: info.device_type,
+                backend: info.backend,
+                driver: info.driver,
+                driver_info: info.driver_info,
+                max_texture_dimension: device.limits().max_texture_dimension_2d,
+            }
+        };
 
-        let egui_renderer = egui_wgpu::Renderer::new(&device, surface_format, None, 1, false);
+        info!(
+            name = adapter_info.name,
+            backend = ?adapter_info.backend,
+            driver = adapter_info.driver,
+            driver_info = adapter_info.driver_info,
+            "using adapter"
+        );
 
-        Self {
+        let max_supported_anisotropy = if adapter
+            .get_downlevel_capabilities()
+            .flags
+            .contains(wgpu::DownlevelFlags::ANISOTROPIC_FILTERING)
+        {
+            MAX_ANISOTROPY
+        } else {
+            1
+        };
+
+        let surface_capabilities = match &surface {
+            Some(surface) => surface.get_capabilities(&adapter),
+            None => wgpu::SurfaceCapabilities {
+                formats: vec![SCENE_COLOR_FORMAT],
+                present_modes: vec![wgpu::PresentMode::Fifo],
+                alpha_modes: vec![wgpu::CompositeAlphaMode::Opaque],
+                usages: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            },
+        };
+        // shaders output linear color and the surface encodes it to sRGB
+        let surface_format = match surface_capabilities
+            .formats
+            .iter()
+            .find(|format| format.is_srgb())
+        {
+            Some(format) => *format,
+            None => {
+                let format = surface_capabilities.formats[0];
+                warn!(
+                    ?format,
+                    "surface has no sRGB format, colors will look too dark"
+                );
+                format
+            }
+        };
+
+        // egui blends in gamma space, so it draws to a non-sRGB view of the
+        // same surface
+        let ui_format = surface_format.remove_srgb_suffix();
+
+        // COPY_SRC is only needed for frame capture, so it's optional
+        let surface_usage = wgpu::TextureUsages::RENDER_ATTACHMENT
+            | (surface_capabilities.usages & wgpu::TextureUsages::COPY_SRC);
+
+        let frame_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("frame"),
+                entries: &[wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                }],
+            });
+
+        let frame_uniforms = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("frame"),
+            size: std::mem::size_of::<FrameUniforms>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let frame_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("frame"),
+            layout: &frame_bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: frame_uniforms.as_entire_binding(),
+            }],
+        });
+
+        let objects = ObjectBuffer::new(&device);
+
+        let environment_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("environment"),
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Texture {
+                            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                            view_dimension: wgpu::TextureViewDimension::Cube,
+                            multisampled: false,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                        count: None,
+                    },
+                ],
+            });
+
+        let environment_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("environment"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let egui_renderer = egui_wgpu::Renderer::new(&device, ui_format, None, 1, false);
+
+        let mut renderer = Self {
             instance,
             device,
             surface,
             queue,
+            adapter_info,
             surface_format,
+            ui_format,
+            surface_usage,
+            surface_size,
+            frames_in_flight: 2,
+            frame_index: 0,
+            supported_present_modes: surface_capabilities.present_modes.clone(),
+            supported_alpha_modes: surface_capabilities.alpha_modes.clone(),
+            present_mode: wgpu::PresentMode::AutoVsync,
+            max_supported_anisotropy,
+            anisotropy: 1,
+            clear_color: None,
+            depth_prepass: false,
+
+            capture_requested: false,
+            capture: None,
+            pass_timings: None,
+            frame_draw_calls: 0,
+            previous_frame_draw_calls: 0,
+
+            frame_bind_group_layout,
+            frame_uniforms,
+            frame_bind_group,
+            objects,
+
+            environment_bind_group_layout,
+            environment_sampler,
+            environment_bind_groups: AHashMap::new(),
+            skybox_pipeline: None,
+
+            debug_pipeline: None,
+            debug_depth_tested_pipeline: None,
+            debug_vertex_buffer: None,
+            debug_vertex_count: 0,
+            debug_depth_tested_vertex_count: 0,
+
+            views_pipeline: None,
+            views_target: None,
+            views_pending: false,
 
             materials: AHashMap::new(),
+            // replaced below, creating materials needs a renderer
+            error_material: MaterialHandle(Uuid::nil()),
             meshes: AHashMap::new(),
+            mesh_arena: MeshArena::new(),
+            textures: AHashMap::new(),
+            samplers: AHashMap::new(),
             egui_renderer,
             egui_render_targets: AHashMap::new(),
-        }
+        };
+
+        renderer.error_material = renderer
+            .upload_material(&MaterialDesc {
+                label: "error",
+                vertex_shader: &error_vs,
+                fragment_shader: &error_fs,
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                bindings: &[],
+            })
+            .unwrap();
+
+        renderer
     }
 
-    pub fn upload_material(&mut self, desc: &MaterialDesc) -> Uuid {
-        let (vs, fs) = unsafe {
-            let vs = self
-                .device
-                .create_shader_module_spirv(&wgpu::ShaderModuleDescriptorSpirV {
-                    label: None,
-                    source: Cow::Borrowed(bytemuck::cast_slice(desc.vertex_shader.data())),
-                });
-            let fs = self
-                .device
-                .create_shader_module_spirv(&wgpu::ShaderModuleDescriptorSpirV {
-                    label: None,
-                    source: Cow::Borrowed(bytemuck::cast_slice(desc.fragment_shader.data())),
-                });
+    pub fn upload_material(
+        &mut self,
+        desc: &MaterialDesc,
+    ) -> Result<MaterialHandle, RendererError> {
+        let vs = self.shader_module(desc.label, desc.vertex_shader, ShaderStage::Vertex)?;
+        let fs = self.shader_module(desc.label, desc.fragment_shader, ShaderStage::Fragment)?;
 
-            (vs, fs)
-        };
+        let vertex_layout = crate::asset::Vertex::layout();
+
+        spirv::check_vertex_inputs(desc.vertex_shader.data(), "vs_main", &vertex_layout).map_err(
+            |err| match err {
+                VertexInputError::Missing { location } => RendererError::MissingVertexAttribute {
+                    label: desc.label.to_owned(),
+                    location,
+                },
+                VertexInputError::Mismatch {
+                    location,
+                    shader,
+                    layout,
+                } => RendererError::VertexFormatMismatch {
+                    label: desc.label.to_owned(),
+                    location,
+                    shader: shader.to_string(),
+                    layout,
+                },
+            },
+        )?;
+
+        let layout_entries = desc
+            .bindings
+            .iter()
+            .enumerate()
+            .map(|(binding, resource)| {
+                let ty = match resource {
+                    MaterialBinding::Texture(handle) => wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: self
+                            .textures
+                            .get(handle)
+                            .ok_or_else(|| RendererError::MissingBinding {
+                                label: desc.label.to_owned(),
+                                binding: binding as u32,
+                            })?
+                            .view_dimension,
+                        multisampled: false,
+                    },
+                    MaterialBinding::Sampler(_) => {
+                        wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering)
+                    }
+                };
+
+                Ok(wgpu::BindGroupLayoutEntry {
+                    binding: binding as u32,
+                    visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                    ty,
+                    count: None,
+                })
+            })
+            .collect::<Result<Vec<_>, RendererError>>()?;
 
         let bind_group_layout =
             self.device
                 .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-                    entries: &[],
-                    label: None,
+                    entries: &layout_entries,
+                    label: Some(desc.label),
                 });
 
+        let bind_group =
+            self.create_material_bind_group(desc.label, &bind_group_layout, desc.bindings)?;
+
         let pipeline_layout = self
             .device
             .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-                label: None,
-                bind_group_layouts: &[&bind_group_layout],
+                label: Some(desc.label),
+                bind_group_layouts: &[
+                    &self.frame_bind_group_layout,
+                    &bind_group_layout,
+                    self.objects.layout(),
+                ],
                 push_constant_ranges: &[],
             });
 
-        let pipeline = self
-            .device
-            .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-                vertex: wgpu::VertexState {
-                    module: &vs,
-                    entry_point: "vs_main",
-                    buffers: &[crate::asset::Vertex::layout()],
-                    compilation_options: wgpu::PipelineCompilationOptions::default(),
-                },
-                fragment: Some(wgpu::FragmentState {
-                    module: &fs,
-                    entry_point: "fs_main",
-                    targets: &[Some(self.surface_format.into())],
-                    compilation_options: wgpu::PipelineCompilationOptions::default(),
-                }),
-                label: None,
-                layout: Some(&pipeline_layout),
-                primitive: wgpu::PrimitiveState::default(),
-                depth_stencil: None,
-                multisample: wgpu::MultisampleState::default(),
-                multiview: None,
-                cache: None,
-            });
+        let color_targets = [
+            Some(SCENE_COLOR_FORMAT.into()),
+            Some(OBJECT_ID_FORMAT.into()),
+        ];
+
+        // every variant shares the vertex stage, so that the depth prepass
+        // produces exactly the depth the color pass compares against
+        let create_pipeline = |color: bool, depth_write_enabled, depth_compare| {
+            self.capture_errors(|device| {
+                device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                    vertex: wgpu::VertexState {
+                        module: &vs,
+                        entry_point: "vs_main",
+                        buffers: std::slice::from_ref(&vertex_layout),
+                        compilation_options: wgpu::PipelineCompilationOptions::default(),
+                    },
+                    fragment: color.then(|| wgpu::FragmentState {
+                        module: &fs,
+                        entry_point: "fs_main",
+                        targets: &color_targets,
+                        compilation_options: wgpu::PipelineCompilationOptions::default(),
+                    }),
+                    label: Some(desc.label),
+                    layout: Some(&pipeline_layout),
+                    primitive: wgpu::PrimitiveState {
+                        topology: desc.topology,
+                        ..Default::default()
+                    },
+                    depth_stencil: Some(wgpu::DepthStencilState {
+                        format: DEPTH_FORMAT,
+                        depth_write_enabled,
+                        depth_compare,
+                        stencil: wgpu::StencilState::default(),
+                        bias: wgpu::DepthBiasState::default(),
+                    }),
+                    multisample: wgpu::MultisampleState::default(),
+                    multiview: None,
+                    cache: None,
+                })
+            })
+            .map_err(|source| RendererError::Pipeline {
+                label: desc.label.to_owned(),
+                source,
+            })
+        };
+
+        let pipeline = create_pipeline(true, true, wgpu::CompareFunction::Less)?;
+        let depth_pipeline = create_pipeline(false, true, wgpu::CompareFunction::Less)?;
+        let depth_equal_pipeline = create_pipeline(true, false, wgpu::CompareFunction::Equal)?;
 
-        let id = Uuid::new_v4();
+        let id = MaterialHandle(Uuid::new_v4());
 
         self.materials.insert(
             id,
             GpuMaterial {
+                label: desc.label.to_owned(),
+                bindings: desc.bindings.to_vec(),
                 bind_group_layout,
+                bind_group,
                 pipeline_layout,
                 pipeline,
+                depth_pipeline,
+                depth_equal_pipeline,
+                ref_count: 1,
             },
         );
 
-        id
-    }
-
-    fn upload_model(&mut self, model: &Model) {
-        for mesh in model.meshes() {
-            self.upload_mesh(mesh);
-        }
+        Ok(id)
     }
 
-    fn upload_mesh(&mut self, mesh: &Mesh) {
-        let renderable_mesh_id = Uuid::new_v4();
-        info!(%renderable_mesh_id);
+    fn create_material_bind_group(
+        &self,
+        label: &str,
+        layout: &wgpu::BindGroupLayout,
+        bindings: &[MaterialBinding],
+    ) -> Result<wgpu::BindGroup, RendererError> {
+        let entries = bindings
+            .iter()
+            .enumerate()
+            .map(|(binding, resource)| {
+                let resource = match resource {
+                    MaterialBinding::Texture(handle) => self
+                        .textures
+                        .get(handle)
+                        .map(|texture| wgpu::BindingResource::TextureView(&texture.view)),
+                    MaterialBinding::Sampler(handle) => self
+                        .samplers
+                        .get(handle)
+                        .map(|sampler| wgpu::BindingResource::Sampler(&sampler.sampler)),
+                };
 
-        let mesh_data_size = std::mem::size_of_val(mesh.data()) as u64;
+                Ok(wgpu::BindGroupEntry {
+                    binding: binding as u32,
+                    resource: resource.ok_or_else(|| RendererError::MissingBinding {
+                        label: label.to_owned(),
+                        binding: binding as u32,
+                    })?,
+                })
+            })
+            .collect::<Result<Vec<_>, RendererError>>()?;
 
-        let buffer = self
-            .device
-            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                label: None,
-                contents: bytemuck::cast_slice(mesh.data()),
-                usage: wgpu::BufferUsages::VERTEX,
-            });
+        Ok(self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some(label),
+            layout,
+            entries: &entries,
+        }))
+    }
 
-        self.meshes.insert(
-            renderable_mesh_id,
-            GpuMesh {
-                vertex_count: mesh.vertex_count(),
-                buffer,
-            },
-        );
+    pub fn error_material(&self) -> MaterialHandle {
+        self.error_material
     }
 
-    pub fn resize(&mut self, size: Extent2D) {
-        self.surface.configure(
-            &self.device,
-            &wgpu::SurfaceConfiguration {
-                usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-                format: self.surface_format,
-                width: size.width,
-                height: size.height,
-                present_mode: wgpu::PresentMode::AutoVsync,
-                desired_maximum_frame_latency: 2,
-                alpha_mode: wgpu::CompositeAlphaMode::Auto,
-                view_formats: Vec::new(),
-            },
-        );
+    pub fn retain_material(&mut self, handle: MaterialHandle) {
+        match self.materials.get_mut(&handle) {
+            Some(material) => material.ref_count += 1,
+            None => warn!(?handle, "retaining a material that isn't loaded"),
+        }
     }
 
-    pub fn create_egui_render_target(&mut self, size: Extent2D) -> egui::TextureId {
-        let texture = self.device.create_texture(&wgpu::TextureDescriptor {
-            label: None,
-            size: wgpu::Extent3d {
-                width: size.width,
-                height: size.height,
-                depth_or_array_layers: 1,
-            },
-            mip_level_count: 1,
-            sample_count: 1,
-            dimension: wgpu::TextureDimension::D2,
-            format: wgpu::TextureFormat::Rgba8UnormSrgb,
-            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
-            view_formats: &[],
-        });
+    /// Meshes still using the material are drawn with [`Renderer::error_material`].
+    pub fn unload_material(&mut self, handle: MaterialHandle) {
+        if handle == self.error_material {
+            warn!("the error material can't be unloaded");
+            return;
+        }
 
-        let texture_view = texture.create_view(&Default::default());
-        let texture_id = self.egui_renderer.register_native_texture(
-            &self.device,
-            &texture_view,
-            wgpu::FilterMode::Nearest,
-        );
+        let Some(material) = self.materials.get_mut(&handle) else {
+            warn!(?handle, "unloading a material that isn't loaded");
+            return;
+        };
 
-        self.egui_render_targets
-            .insert(texture_id, (texture_view, size));
+        material.ref_count -= 1;
 
-        texture_id
+        if material.ref_count == 0 {
+            self.materials.remove(&handle);
+        }
     }
 
-    fn resize_egui_render_target(&mut self, texture_id: egui::TextureId, size: Extent2D) {
-        self.egui_render_targets
-            .entry(texture_id)
-            .and_modify(|(view, rt_size)| {
-                if size != *rt_size {
-                    return;
-                }
-
-                let texture = self.device.create_texture(&wgpu::TextureDescriptor {
-                    label: None,
-                    size: wgpu::Extent3d {
-                        width: size.width,
-                        height: size.height,
-                        depth_or_array_layers: 1,
-                    },
-                    mip_level_count: 1,
-                    sample_count: 1,
-                    dimension: wgpu::TextureDimension::D2,
-                    format: wgpu::TextureFormat::Rgba8UnormSrgb,
-                    usage: wgpu::TextureUsages::RENDER_ATTACHMENT
-                        | wgpu::TextureUsages::TEXTURE_BINDING,
-                    view_formats: &[],
-                });
-
-                let texture_view = texture.create_view(&Default::default());
+    fn shader_module(
+        &self,
+        label: &str,
+        shader: &Shader,
+        stage: ShaderStage,
+    ) -> Result<wgpu::ShaderModule, RendererError> {
+        // SAFETY: SPIR-V passthrough skips wgpu's own validation, anything
+        // the driver rejects is caught as a validation error
+        self.capture_errors(|device| unsafe {
+            device.create_shader_module_spirv(&wgpu::ShaderModuleDescriptorSpirV {
+                label: Some(label),
+                source: Cow::Borrowed(bytemuck::cast_slice(shader.data())),
+            })
+        })
+        .map_err(|source| RendererError::Shader {
+            label: label.to_owned(),
+            stage,
+            source,
+        })
+    }
 
-                self.egui_renderer.update_egui_texture_from_wgpu_texture(
-                    &self.device,
-                    &texture_view,
-                    wgpu::FilterMode::Nearest,
-                    texture_id,
-                );
+    /// Errors caught here don't reach the uncaptured error handler, which panics.
+    fn capture_errors<T>(&self, f: impl FnOnce(&wgpu::Device) -> T) -> Result<T, wgpu::Error> {
+        self.device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let value = f(&self.device);
 
-                *view = texture_view;
-            });
+        match self.device.pop_error_scope().block_on() {
+            Some(err) => Err(err),
+            None => Ok(value),
+        }
     }
 
-    pub fn render_scene_to_egui_texture(
+    pub fn init_skybox(
         &mut self,
-        texture_id: egui::TextureId,
-        size: Extent2D,
-        scene: &Scene,
-    ) {
-        self.resize_egui_render_target(texture_id, size);
+        vertex_shader: &Shader,
+        fragment_shader: &Shader,
+    ) -> Result<(), RendererError> {
+        let vs = self.shader_module("skybox", vertex_shader, ShaderStage::Vertex)?;
+        let fs = self.shader_module("skybox", fragment_shader, ShaderStage::Fragment)?;
 
-        let (view, _) = self.egui_render_targets.get(&texture_id).unwrap();
+        let pipeline_layout = self
+            .device
+            .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("skybox"),
+                bind_group_layouts: &[
+                    &self.frame_bind_group_layout,
+                    &self.environment_bind_group_layout,
+                ],
+                push_constant_ranges: &[],
+            });
 
-        let mut encoder = self.device.create_command_encoder(&Default::default());
+        let pipeline = self.create_fullscreen_pipeline(
+            "skybox",
+            &vs,
+            &fs,
+            &pipeline_layout,
+            &[
+                Some(SCENE_COLOR_FORMAT.into()),
+                Some(OBJECT_ID_FORMAT.into()),
+            ],
+            Some(overlay_depth_state()),
+        )?;
 
-        {
-            let rp = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                label: None,
-                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view,
-                    resolve_target: None,
-                    ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(wgpu::Color {
-                            r: ((scene.bg_color >> 24) & 0xFF) as f64 / 255.0,
-                            g: ((scene.bg_color >> 16) & 0xFF) as f64 / 255.0,
-                            b: ((scene.bg_color >> 8) & 0xFF) as f64 / 255.0,
-                            a: ((scene.bg_color) & 0xFF) as f64 / 255.0,
-                        }),
-                        store: wgpu::StoreOp::Store,
-                    },
-                })],
-                depth_stencil_attachment: None,
-                timestamp_writes: None,
-                occlusion_query_set: None,
-            });
-        }
+        self.skybox_pipeline = Some(pipeline);
 
-        self.queue.submit([encoder.finish()]);
+        Ok(())
     }
 
-    pub fn render(
+    pub fn init_views(
         &mut self,
-        camera_transform: Mat4,
-        scene: &Scene,
-        prepared_ui: &PreparedUi,
-        viewport_extent: Extent2D,
-    ) {
-        let frame = self.surface.get_current_texture().unwrap();
-        let frame_view = frame.texture.create_view(&Default::default());
+        vertex_shader: &Shader,
+        fragment_shader: &Shader,
+    ) -> Result<(), RendererError> {
+        let vs = self.shader_module("views", vertex_shader, ShaderStage::Vertex)?;
+        let fs = self.shader_module("views", fragment_shader, ShaderStage::Fragment)?;
 
-        let mut encoder = self.device.create_command_encoder(&Default::default());
+        let bind_group_layout =
+            self.device
+                .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                    label: Some("views"),
+                    entries: &[wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Texture {
+                            sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                            view_dimension: wgpu::TextureViewDimension::D2,
+                            multisampled: false,
+                        },
+                        count: None,
+                    }],
+                });
 
-        for (id, delta) in &prepared_ui.textures_delta.set {
-            self.egui_renderer
-                .update_texture(&self.device, &self.queue, *id, delta);
-        }
+        let pipeline_layout = self
+            .device
+            .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("views"),
+                bind_group_layouts: &[&bind_group_layout],
+                push_constant_ranges: &[],
+            });
 
-        self.egui_renderer.update_buffers(
-            &self.device,
-            &self.queue,
-            &mut encoder,
+        let pipeline = self.create_fullscreen_pipeline(
+            "views",
+            &vs,
+            &fs,
+            &pipeline_layout,
+            &[Some(self.surface_format.into())],
+            None,
+        )?;
+
+        self.views_pipeline = Some((bind_group_layout, pipeline));
+
+        Ok(())
+    }
+
+    fn create_fullscreen_pipeline(
+        &self,
+        label: &str,
+        vs: &wgpu::ShaderModule,
+        fs: &wgpu::ShaderModule,
+        layout: &wgpu::PipelineLayout,
+        targets: &[Option<wgpu::ColorTargetState>],
+        depth_stencil: Option<wgpu::DepthStencilState>,
+    ) -> Result<wgpu::RenderPipeline, RendererError> {
+        self.capture_errors(|device| {
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                vertex: wgpu::VertexState {
+                    module: vs,
+                    entry_point: "vs_main",
+                    buffers: &[],
+                    compilation_options: wgpu::PipelineCompilationOptions::default(),
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: fs,
+                    entry_point: "fs_main",
+                    targets,
+                    compilation_options: wgpu::PipelineCompilationOptions::default(),
+                }),
+                label: Some(label),
+                layout: Some(layout),
+                primitive: wgpu::PrimitiveState::default(),
+                depth_stencil,
+                multisample: wgpu::MultisampleState::default(),
+                multiview: None,
+                cache: None,
+            })
+        })
+        .map_err(|source| RendererError::Pipeline {
+            label: label.to_owned(),
+            source,
+        })
+    }
+
+    pub fn init_debug_draw(
+        &mut self,
+        vertex_shader: &Shader,
+        fragment_shader: &Shader,
+    ) -> Result<(), RendererError> {
+        let vs = self.shader_module("debug", vertex_shader, ShaderStage::Vertex)?;
+        let fs = self.shader_module("debug", fragment_shader, ShaderStage::Fragment)?;
+
+        let pipeline_layout = self
+            .device
+            .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("debug"),
+                bind_group_layouts: &[&self.frame_bind_group_layout],
+                push_constant_ranges: &[],
+            });
+
+        let create_pipeline = |label: &str, depth_stencil: wgpu::DepthStencilState| {
+            self.capture_errors(|device| {
+                device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                    vertex: wgpu::VertexState {
+                        module: &vs,
+                        entry_point: "vs_main",
+                        buffers: &[DebugVertex::layout()],
+                        compilation_options: wgpu::PipelineCompilationOptions::default(),
+                    },
+                    fragment: Some(wgpu::FragmentState {
+                        module: &fs,
+                        entry_point: "fs_main",
+                        targets: &[
+                            Some(wgpu::ColorTargetState {
+                                format: SCENE_COLOR_FORMAT,
+                                blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                                write_mask: wgpu::ColorWrites::ALL,
+                            }),
+                            Some(wgpu::ColorTargetState {
+                                format: OBJECT_ID_FORMAT,
+                                blend: None,
+                                write_mask: wgpu::ColorWrites::empty(),
+                            }),
+                        ],
+                        compilation_options: wgpu::PipelineCompilationOptions::default(),
+                    }),
+                    label: Some(label),
+                    layout: Some(&pipeline_layout),
+                    primitive: wgpu::PrimitiveState {
+                        topology: wgpu::PrimitiveTopology::LineList,
+                        ..Default::default()
+                    },
+                    depth_stencil: Some(depth_stencil),
+                    multisample: wgpu::MultisampleState::default(),
+                    multiview: None,
+                    cache: None,
+                })
+            })
+            .map_err(|source| RendererError::Pipeline {
+                label: label.to_owned(),
+                source,
+            })
+        };
+
+        let pipeline = create_pipeline("debug", overlay_depth_state())?;
+        // lines lying on a surface would z-fight with it, so pull them
+        // slightly towards the camera
+        let depth_tested_pipeline = create_pipeline(
+            "debug depth tested",
+            wgpu::DepthStencilState {
+                format: DEPTH_FORMAT,
+                depth_write_enabled: false,
+                depth_compare: wgpu::CompareFunction::LessEqual,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState {
+                    constant: -2,
+                    slope_scale: -1.0,
+                    clamp: 0.0,
+                },
+            },
+        )?;
+
+        self.debug_pipeline = Some(pipeline);
+        self.debug_depth_tested_pipeline = Some(depth_tested_pipeline);
+
+        Ok(())
+    }
+
+    pub fn upload_debug_draw(&mut self, debug_draw: &DebugDraw) {
+        // depth tested lines go first so they're drawn before the overlay
+        let vertices: Vec<DebugVertex> = debug_draw
+            .depth_tested_vertices()
+            .iter()
+            .chain(debug_draw.vertices())
+            .copied()
+            .collect();
+        let data: &[u8] = bytemuck::cast_slice(&vertices);
+
+        self.debug_depth_tested_vertex_count = debug_draw.depth_tested_vertices().len() as u32;
+        self.debug_vertex_count = debug_draw.vertices().len() as u32;
+
+        if data.is_empty() {
+            return;
+        }
+
+        let fits = self
+            .debug_vertex_buffer
+            .as_ref()
+            .is_some_and(|buffer| buffer.size() >= data.len() as u64);
+
+        if !fits {
+            self.debug_vertex_buffer = Some(self.device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("debug vertices"),
+                size: (data.len() as u64).next_power_of_two(),
+                usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            }));
+        }
+
+        let buffer = self.debug_vertex_buffer.as_ref().unwrap();
+        self.queue.write_buffer(buffer, 0, data);
+    }
+
+    /// Faces in +X, -X, +Y, -Y, +Z, -Z order.
+    pub fn upload_cubemap(&mut self, faces: &[image::RgbaImage; 6]) -> TextureHandle {
+        let (size, _) = faces[0].dimensions();
+
+        for face in faces {
+            assert_eq!(
+                face.dimensions(),
+                (size, size),
+                "cubemap faces must be square and match"
+            );
+        }
+
+        let texture = self.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("cubemap"),
+            size: wgpu::Extent3d {
+                width: size,
+                height: size,
+                depth_or_array_layers: 6,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+
+        for (layer, face) in faces.iter().enumerate() {
+            self.queue.write_texture(
+                wgpu::ImageCopyTexture {
+                    texture: &texture,
+                    mip_level: 0,
+                    origin: wgpu::Origin3d {
+                        x: 0,
+                        y: 0,
+                        z: layer as u32,
+                    },
+                    aspect: wgpu::TextureAspect::All,
+                },
+                face,
+                wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(size * 4),
+                    rows_per_image: None,
+                },
+                wgpu::Extent3d {
+                    width: size,
+                    height: size,
+                    depth_or_array_layers: 1,
+                },
+            );
+        }
+
+        let view = texture.create_view(&wgpu::TextureViewDescriptor {
+            dimension: Some(wgpu::TextureViewDimension::Cube),
+            ..Default::default()
+        });
+
+        let bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("environment"),
+            layout: &self.environment_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&self.environment_sampler),
+                },
+            ],
+        });
+
+        let id = TextureHandle(Uuid::new_v4());
+
+        self.textures.insert(
+            id,
+            GpuTexture {
+                texture,
+                view,
+                view_dimension: wgpu::TextureViewDimension::Cube,
+            },
+        );
+        self.environment_bind_groups.insert(id, bind_group);
+
+        id
+    }
+
+    fn upload_model(&mut self, model: &Model) -> Vec<MeshHandle> {
+        model.meshes().map(|mesh| self.upload_mesh(mesh)).collect()
+    }
+
+    pub fn upload_mesh(&mut self, mesh: &Mesh) -> MeshHandle {
+        let renderable_mesh_id = MeshHandle(Uuid::new_v4());
+        info!(?renderable_mesh_id);
+
+        let allocation = self.mesh_arena.upload(
+            &self.device,
+            &self.queue,
+            bytemuck::cast_slice(mesh.data()),
+            mesh.indices(),
+        );
+
+        self.meshes.insert(
+            renderable_mesh_id,
+            GpuMesh {
+                allocation,
+                ref_count: 1,
+            },
+        );
+
+        renderable_mesh_id
+    }
+
+    pub fn is_mesh_loaded(&self, handle: MeshHandle) -> bool {
+        self.meshes.contains_key(&handle)
+    }
+
+    pub fn retain_mesh(&mut self, handle: MeshHandle) {
+        match self.meshes.get_mut(&handle) {
+            Some(mesh) => mesh.ref_count += 1,
+            None => warn!(?handle, "retaining a mesh that isn't loaded"),
+        }
+    }
+
+    /// Uploads reusing the freed space are ordered after frames already
+    /// submitted, so this is fine to call at any point.
+    pub fn unload_mesh(&mut self, handle: MeshHandle) {
+        let Some(mesh) = self.meshes.get_mut(&handle) else {
+            warn!(?handle, "unloading a mesh that isn't loaded");
+            return;
+        };
+
+        mesh.ref_count -= 1;
+
+        if mesh.ref_count == 0 {
+            let mesh = self.meshes.remove(&handle).unwrap();
+            self.mesh_arena.free(&mesh.allocation);
+        }
+    }
+
+    /// Mips are downsampled on the CPU, in linear space.
+    pub fn upload_texture(
+        &mut self,
+        image: &image::RgbaImage,
+        generate_mips: bool,
+    ) -> TextureHandle {
+        let (width, height) = image.dimensions();
+
+        let mip_level_count = if generate_mips {
+            mip_level_count(width, height)
+        } else {
+            1
+        };
+
+        let texture = self.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("texture"),
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+
+        let mut level_image = image.clone();
+        let mut linear_image = (mip_level_count > 1).then(|| srgb_image_to_linear(image));
+
+        for mip_level in 0..mip_level_count {
+            if let Some(linear) = linear_image.as_mut().filter(|_| mip_level > 0) {
+                // averaging sRGB-encoded values would darken every level
+                *linear = downsample(linear);
+                level_image = linear_image_to_srgb(linear);
+            }
+
+            let (width, height) = level_image.dimensions();
+
+            self.queue.write_texture(
+                wgpu::ImageCopyTexture {
+                    texture: &texture,
+                    mip_level,
+                    origin: wgpu::Origin3d::ZERO,
+                    aspect: wgpu::TextureAspect::All,
+                },
+                &level_image,
+                wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(width * 4),
+                    rows_per_image: None,
+                },
+                wgpu::Extent3d {
+                    width,
+                    height,
+                    depth_or_array_layers: 1,
+                },
+            );
+        }
+
+        let view = texture.create_view(&Default::default());
+
+        let id = TextureHandle(Uuid::new_v4());
+
+        self.textures.insert(
+            id,
+            GpuTexture {
+                texture,
+                view,
+                view_dimension: wgpu::TextureViewDimension::D2,
+            },
+        );
+
+        id
+    }
+
+    pub fn mesh_arena_stats(&self) -> MeshArenaStats {
+        self.mesh_arena.stats()
+    }
+
+    pub fn adapter_info(&self) -> &AdapterInfo {
+        &self.adapter_info
+    }
+
+    pub fn create_sampler(&mut self, desc: &SamplerDesc) -> SamplerHandle {
+        let sampler = self.device.create_sampler(&desc.to_wgpu(self.anisotropy));
+
+        let id = SamplerHandle(Uuid::new_v4());
+
+        self.samplers.insert(
+            id,
+            GpuSampler {
+                desc: *desc,
+                sampler,
+            },
+        );
+
+        id
+    }
+
+    pub fn anisotropy(&self) -> u16 {
+        self.anisotropy
+    }
+
+    pub fn set_anisotropy(&mut self, anisotropy: u16) {
+        let clamped = anisotropy.clamp(1, self.max_supported_anisotropy);
+
+        if clamped != anisotropy {
+            warn!(anisotropy, "anisotropy clamped to {clamped}");
+        }
+
+        if clamped == self.anisotropy {
+            return;
+        }
+
+        self.anisotropy = clamped;
+
+        for sampler in self.samplers.values_mut() {
+            sampler.sampler = self
+                .device
+                .create_sampler(&sampler.desc.to_wgpu(self.anisotropy));
+        }
+
+        // bind groups hold on to the samplers they were created with
+        let rebuilt: Vec<_> = self
+            .materials
+            .iter()
+            .filter(|(_, material)| {
+                material
+                    .bindings
+                    .iter()
+                    .any(|binding| matches!(binding, MaterialBinding::Sampler(_)))
+            })
+            .filter_map(|(handle, material)| {
+                self.create_material_bind_group(
+                    &material.label,
+                    &material.bind_group_layout,
+                    &material.bindings,
+                )
+                .ok()
+                .map(|bind_group| (*handle, bind_group))
+            })
+            .collect();
+
+        for (handle, bind_group) in rebuilt {
+            self.materials.get_mut(&handle).unwrap().bind_group = bind_group;
+        }
+    }
+
+    fn write_frame_uniforms(&self, camera: &Camera, aspect_ratio: f32, scene: &Scene) {
+        let lighting = scene.lighting();
+        let ambient = lighting.ambient_color.to_array();
+
+        let view_projection = camera.view_projection(aspect_ratio);
+
+        let uniforms = FrameUniforms {
+            view_projection,
+            inverse_view_projection: view_projection.inverse(),
+            camera_position: camera.position.extend(1.0),
+            ambient: Vec4::new(
+                ambient[0] * lighting.ambient_intensity,
+                ambient[1] * lighting.ambient_intensity,
+                ambient[2] * lighting.ambient_intensity,
+                lighting.exposure,
+            ),
+        };
+
+        self.queue
+            .write_buffer(&self.frame_uniforms, 0, bytemuck::bytes_of(&uniforms));
+    }
+
+    pub fn read_buffer(&self, buffer: &wgpu::Buffer) -> Vec<u8> {
+        let readback_buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("readback"),
+            size: buffer.size(),
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = self.device.create_command_encoder(&Default::default());
+        encoder.copy_buffer_to_buffer(buffer, 0, &readback_buffer, 0, buffer.size());
+        self.queue.submit([encoder.finish()]);
+
+        self.read_mapped(&readback_buffer)
+    }
+
+    fn read_mapped(&self, buffer: &wgpu::Buffer) -> Vec<u8> {
+        let (tx, rx) = crossbeam_channel::bounded(1);
+
+        let slice = buffer.slice(..);
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = tx.send(result);
+        });
+
+        self.device.poll(wgpu::Maintain::Wait);
+
+        rx.recv().unwrap().expect("failed to map readback buffer");
+
+        let data = slice.get_mapped_range().to_vec();
+        buffer.unmap();
+
+        data
+    }
+
+    pub fn is_headless(&self) -> bool {
+        self.surface.is_none()
+    }
+
+    pub fn wait_idle(&self) {
+        self.device.poll(wgpu::Maintain::Wait);
+    }
+
+    pub fn resize(&mut self, size: Extent2D) {
+        self.surface_size = size;
+        self.configure_surface();
+    }
+
+    pub fn frames_in_flight(&self) -> u32 {
+        self.frames_in_flight
+    }
+
+    /// Same for every system in a step, moves on once [`Renderer::render`]
+    /// presents the frame.
+    pub fn current_frame_index(&self) -> u32 {
+        self.frame_index
+    }
+
+    pub fn set_frames_in_flight(&mut self, frames_in_flight: u32) {
+        let clamped = frames_in_flight.clamp(1, MAX_FRAMES_IN_FLIGHT);
+
+        if clamped != frames_in_flight {
+            warn!(frames_in_flight, "frames in flight clamped to {clamped}");
+        }
+
+        if clamped == self.frames_in_flight {
+            return;
+        }
+
+        self.frames_in_flight = clamped;
+        self.frame_index = 0;
+        self.configure_surface();
+    }
+
+    pub fn supported_present_modes(&self) -> &[wgpu::PresentMode] {
+        &self.supported_present_modes
+    }
+
+    pub fn present_mode(&self) -> wgpu::PresentMode {
+        self.present_mode
+    }
+
+    pub fn set_present_mode(&mut self, present_mode: wgpu::PresentMode) {
+        let supported = matches!(
+            present_mode,
+            wgpu::PresentMode::AutoVsync | wgpu::PresentMode::AutoNoVsync
+        ) || self.supported_present_modes.contains(&present_mode);
+
+        let present_mode = if supported {
+            present_mode
+        } else {
+            warn!(?present_mode, "present mode not supported, using vsync");
+            wgpu::PresentMode::Fifo
+        };
+
+        if present_mode == self.present_mode {
+            return;
+        }
+
+        self.present_mode = present_mode;
+        self.configure_surface();
+    }
+
+    pub fn color_space(&self) -> ColorSpace {
+        if self.surface_format.is_srgb() {
+            ColorSpace::Srgb
+        } else {
+            ColorSpace::Linear
+        }
+    }
+
+    pub fn clear_color(&self) -> Option<Color> {
+        self.clear_color
+    }
+
+    pub fn set_clear_color(&mut self, clear_color: Option<Color>) {
+        let was_translucent = self.is_clear_translucent();
+        self.clear_color = clear_color;
+
+        if self.is_clear_translucent() != was_translucent {
+            self.configure_surface();
+        }
+    }
+
+    pub fn depth_prepass(&self) -> bool {
+        self.depth_prepass
+    }
+
+    pub fn set_depth_prepass(&mut self, depth_prepass: bool) {
+        self.depth_prepass = depth_prepass;
+    }
+
+    fn is_clear_translucent(&self) -> bool {
+        self.clear_color.is_some_and(|color| color.a < 1.0)
+    }
+
+    fn alpha_mode(&self) -> wgpu::CompositeAlphaMode {
+        let premultiplied = wgpu::CompositeAlphaMode::PreMultiplied;
+
+        if self.is_clear_translucent() && self.supported_alpha_modes.contains(&premultiplied) {
+            premultiplied
+        } else {
+            wgpu::CompositeAlphaMode::Auto
+        }
+    }
+
+    fn configure_surface(&mut self) {
+        let Some(surface) = &self.surface else {
+            return;
+        };
+
+        if self.surface_size.is_empty() {
+            return;
+        }
+
+        surface.configure(
+            &self.device,
+            &wgpu::SurfaceConfiguration {
+                usage: self.surface_usage,
+                format: self.surface_format,
+                width: self.surface_size.width,
+                height: self.surface_size.height,
+                present_mode: self.present_mode,
+                desired_maximum_frame_latency: self.frames_in_flight,
+                alpha_mode: self.alpha_mode(),
+                view_formats: if self.ui_format != self.surface_format {
+                    vec![self.ui_format]
+                } else {
+                    Vec::new()
+                },
+            },
+        );
+    }
+
+    pub fn create_egui_render_target(&mut self, size: Extent2D) -> egui::TextureId {
+        let render_target = self.create_render_target(size);
+
+        // viewports are drawn at their physical size, texel for pixel
+        let texture_id = self
+            .egui_renderer
+            .register_native_texture_with_sampler_options(
+                &self.device,
+                &render_target.color_view,
+                SamplerDesc::NEAREST_CLAMP.to_wgpu(1),
+            );
+
+        self.egui_render_targets.insert(texture_id, render_target);
+
+        texture_id
+    }
+
+    fn create_render_target(&self, size: Extent2D) -> RenderTarget {
+        let size = Extent2D {
+            width: size.width.max(1),
+            height: size.height.max(1),
+        };
+
+        let extent = wgpu::Extent3d {
+            width: size.width,
+            height: size.height,
+            depth_or_array_layers: 1,
+        };
+
+        let color = self.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("scene color"),
+            size: extent,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: SCENE_COLOR_FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+
+        let object_id = self.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("object id"),
+            size: extent,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: OBJECT_ID_FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+
+        let depth = self.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("scene depth"),
+            size: extent,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: DEPTH_FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        });
+
+        RenderTarget {
+            color_view: color.create_view(&Default::default()),
+            object_id_view: object_id.create_view(&Default::default()),
+            object_id,
+            depth_view: depth.create_view(&Default::default()),
+            size,
+        }
+    }
+
+    fn resize_egui_render_target(&mut self, texture_id: egui::TextureId, size: Extent2D) {
+        let Some(render_target) = self.egui_render_targets.get(&texture_id) else {
+            return;
+        };
+
+        if render_target.size == size {
+            return;
+        }
+
+        let render_target = self.create_render_target(size);
+
+        self.egui_renderer
+            .update_egui_texture_from_wgpu_texture_with_sampler_options(
+                &self.device,
+                &render_target.color_view,
+                SamplerDesc::NEAREST_CLAMP.to_wgpu(1),
+                texture_id,
+            );
+
+        self.egui_render_targets.insert(texture_id, render_target);
+    }
+
+    fn resize_views_target(&mut self) {
+        let up_to_date = self
+            .views_target
+            .as_ref()
+            .is_some_and(|(render_target, _)| render_target.size == self.surface_size);
+
+        if up_to_date {
+            return;
+        }
+
+        let (bind_group_layout, _) = self.views_pipeline.as_ref().unwrap();
+        let render_target = self.create_render_target(self.surface_size);
+
+        let bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("views"),
+            layout: bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(&render_target.color_view),
+            }],
+        });
+
+        self.views_target = Some((render_target, bind_group));
+    }
+
+    pub fn render_scene_to_egui_texture(
+        &mut self,
+        texture_id: egui::TextureId,
+        size: Extent2D,
+        scene: &Scene,
+        root: NodeHandle,
+        camera: &Camera,
+    ) {
+        // viewports collapse to nothing while the window is minimized
+        if size.is_empty() {
+            return;
+        }
+
+        let start = Instant::now();
+
+        self.resize_egui_render_target(texture_id, size);
+
+        self.write_frame_uniforms(camera, size.aspect_ratio(), scene);
+        let (draws, batches) = self.prepare_scene_draws(scene, root);
+
+        let render_target = self.egui_render_targets.get(&texture_id).unwrap();
+
+        let mut encoder = self.device.create_command_encoder(&Default::default());
+        let draw_calls =
+            self.encode_scene_pass(&mut encoder, render_target, scene, &draws, &batches, None);
+        self.queue.submit([encoder.finish()]);
+
+        self.record_pass("scene", start, draw_calls);
+    }
+
+    /// Does nothing until [`Renderer::init_views`] is called.
+    pub fn render_views(&mut self, scene: &Scene, views: &[ViewDesc]) {
+        if self.views_pipeline.is_none() || self.surface_size.is_empty() {
+            return;
+        }
+
+        let start = Instant::now();
+        let mut draw_calls = 0;
+        let mut cleared = false;
+
+        self.resize_views_target();
+
+        for view in views {
+            let rect = view.pixel_rect(self.surface_size);
+
+            if rect.size.is_empty() {
+                continue;
+            }
+
+            self.write_frame_uniforms(view.camera, rect.size.aspect_ratio(), scene);
+            let (draws, batches) = self.prepare_scene_draws(scene, view.root);
+
+            let (render_target, _) = self.views_target.as_ref().unwrap();
+
+            // every view is its own submission, since they share the frame
+            // and object uniform buffers
+            let mut encoder = self.device.create_command_encoder(&Default::default());
+            draw_calls += self.encode_scene_pass(
+                &mut encoder,
+                render_target,
+                scene,
+                &draws,
+                &batches,
+                Some((rect, !cleared)),
+            );
+            self.queue.submit([encoder.finish()]);
+
+            // the first view clears the whole target
+            cleared = true;
+        }
+
+        self.views_pending = cleared;
+
+        self.record_pass("views", start, draw_calls);
+    }
+
+    fn prepare_scene_draws(
+        &mut self,
+        scene: &Scene,
+        root: NodeHandle,
+    ) -> (Vec<DrawCommand>, Vec<MaterialBatch>) {
+        let mut objects = Vec::new();
+        let mut draws = Vec::new();
+
+        for handle in scene.visible_nodes(root) {
+            let Node::Mesh(mesh) = scene.node(handle).node else {
+                continue;
+            };
+
+            // not uploaded yet, or unloaded while the node is still around
+            let mut mesh_handles = mesh
+                .renderables()
+                .iter()
+                .copied()
+                .filter(|id| self.meshes.contains_key(id))
+                .peekable();
+
+            if mesh_handles.peek().is_none() {
+                continue;
+            }
+
+            let material = mesh
+                .material()
+                .filter(|material| self.materials.contains_key(material))
+                .unwrap_or(self.error_material);
+
+            // the meshes of a node share its object uniforms
+            draws.extend(mesh_handles.map(|mesh_handle| DrawCommand {
+                material,
+                mesh: mesh_handle,
+                object_index: objects.len() as u32,
+            }));
+
+            objects.push(ObjectUniforms {
+                transform: scene.world_transform(handle).matrix(),
+                object_id: handle.index() + 1,
+                _padding: [0; 3],
+            });
+        }
+
+        self.objects.write(&self.device, &self.queue, &objects);
+        let batches = batch_by_material(&mut draws);
+
+        (draws, batches)
+    }
+
+    fn encode_scene_pass(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        render_target: &RenderTarget,
+        scene: &Scene,
+        draws: &[DrawCommand],
+        batches: &[MaterialBatch],
+        viewport: Option<(ViewRect, bool)>,
+    ) -> u32 {
+        let mut draw_calls = 0;
+
+        let clear = viewport.is_none_or(|(_, clear)| clear);
+        let depth_prepass = self.depth_prepass && !draws.is_empty();
+
+        if depth_prepass {
+            let mut rp = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("depth prepass"),
+                color_attachments: &[],
+                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                    view: &render_target.depth_view,
+                    depth_ops: Some(wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(1.0),
+                        store: wgpu::StoreOp::Store,
+                    }),
+                    stencil_ops: None,
+                }),
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+
+            if let Some((rect, _)) = viewport {
+                set_view_rect(&mut rp, rect);
+            }
+
+            rp.set_bind_group(0, &self.frame_bind_group, &[]);
+            draw_calls +=
+                self.draw_meshes(&mut rp, draws, batches, |material| &material.depth_pipeline);
+        }
+
+        let (color_load, object_id_load) = if clear {
+            (
+                wgpu::LoadOp::Clear(scene.bg_color().into()),
+                wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+            )
+        } else {
+            (wgpu::LoadOp::Load, wgpu::LoadOp::Load)
+        };
+
+        let mut rp = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("scene"),
+            color_attachments: &[
+                Some(wgpu::RenderPassColorAttachment {
+                    view: &render_target.color_view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: color_load,
+                        store: wgpu::StoreOp::Store,
+                    },
+                }),
+                Some(wgpu::RenderPassColorAttachment {
+                    view: &render_target.object_id_view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: object_id_load,
+                        store: wgpu::StoreOp::Store,
+                    },
+                }),
+            ],
+            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                view: &render_target.depth_view,
+                depth_ops: Some(wgpu::Operations {
+                    load: if depth_prepass {
+                        wgpu::LoadOp::Load
+                    } else {
+                        wgpu::LoadOp::Clear(1.0)
+                    },
+                    store: wgpu::StoreOp::Discard,
+                }),
+                stencil_ops: None,
+            }),
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+
+        if let Some((rect, _)) = viewport {
+            set_view_rect(&mut rp, rect);
+        }
+
+        rp.set_bind_group(0, &self.frame_bind_group, &[]);
+
+        let skybox = self.skybox_pipeline.as_ref().zip(
+            scene
+                .environment()
+                .and_then(|id| self.environment_bind_groups.get(&id)),
+        );
+
+        if let Some((pipeline, environment)) = skybox {
+            rp.set_pipeline(pipeline);
+            rp.set_bind_group(1, environment, &[]);
+            draw_fullscreen_triangle(&mut rp);
+            draw_calls += 1;
+        }
+
+        draw_calls += self.draw_meshes(&mut rp, draws, batches, |material| {
+            if depth_prepass {
+                &material.depth_equal_pipeline
+            } else {
+                &material.pipeline
+            }
+        });
+
+        if let Some(vertex_buffer) = &self.debug_vertex_buffer {
+            let depth_tested_end = self.debug_depth_tested_vertex_count;
+            let overlay_end = depth_tested_end + self.debug_vertex_count;
+            let ranges = [
+                (&self.debug_depth_tested_pipeline, 0..depth_tested_end),
+                (&self.debug_pipeline, depth_tested_end..overlay_end),
+            ];
+
+            for (pipeline, vertices) in ranges {
+                let Some(pipeline) = pipeline.as_ref().filter(|_| !vertices.is_empty()) else {
+                    continue;
+                };
+
+                rp.set_pipeline(pipeline);
+                rp.set_vertex_buffer(0, vertex_buffer.slice(..));
+                rp.draw(vertices, 0..1);
+                draw_calls += 1;
+            }
+        }
+
+        draw_calls
+    }
+
+    fn draw_meshes(
+        &self,
+        rp: &mut wgpu::RenderPass,
+        draws: &[DrawCommand],
+        batches: &[MaterialBatch],
+        pipeline: impl Fn(&GpuMaterial) -> &wgpu::RenderPipeline,
+    ) -> u32 {
+        let mut draw_calls = 0;
+        let mut bound_page = None;
+
+        for batch in batches {
+            let material = &self.materials[&batch.material];
+
+            rp.set_pipeline(pipeline(material));
+            rp.set_bind_group(1, &material.bind_group, &[]);
+
+            for draw in &draws[batch.draws.clone()] {
+                let allocation = &self.meshes[&draw.mesh].allocation;
+
+                if bound_page != Some(allocation.page) {
+                    let (vertex_buffer, index_buffer) = self.mesh_arena.buffers(allocation.page);
+
+                    rp.set_vertex_buffer(0, vertex_buffer.slice(..));
+                    rp.set_index_buffer(index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+                    bound_page = Some(allocation.page);
+                }
+
+                rp.set_bind_group(
+                    2,
+                    self.objects.bind_group(),
+                    &[self.objects.offset(draw.object_index)],
+                );
+                rp.draw_indexed(allocation.indices.clone(), allocation.base_vertex(), 0..1);
+                draw_calls += 1;
+            }
+        }
+
+        draw_calls
+    }
+
+    /// `None` if there's no render target to read from at `(x, y)`. Blocks
+    /// until the GPU is done rendering.
+    pub fn pick_object(&self, texture_id: egui::TextureId, x: u32, y: u32) -> Option<Pick> {
+        let render_target = self.egui_render_targets.get(&texture_id)?;
+
+        if x >= render_target.size.width || y >= render_target.size.height {
+            return None;
+        }
+
+        let readback_buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("object id readback"),
+            size: std::mem::size_of::<u32>() as u64,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = self.device.create_command_encoder(&Default::default());
+        encoder.copy_texture_to_buffer(
+            wgpu::ImageCopyTexture {
+                texture: &render_target.object_id,
+                mip_level: 0,
+                origin: wgpu::Origin3d { x, y, z: 0 },
+                aspect: wgpu::TextureAspect::All,
+            },
+            wgpu::ImageCopyBuffer {
+                buffer: &readback_buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: None,
+                    rows_per_image: None,
+                },
+            },
+            wgpu::Extent3d {
+                width: 1,
+                height: 1,
+                depth_or_array_layers: 1,
+            },
+        );
+        self.queue.submit([encoder.finish()]);
+
+        let data = self.read_mapped(&readback_buffer);
+        let object_id = u32::from_ne_bytes(data[..4].try_into().unwrap());
+
+        Some(match object_id.checked_sub(1) {
+            Some(index) => Pick::Object(index),
+            None => Pick::Background,
+        })
+    }
+
+    pub fn render(
+        &mut self,
+        scene: &Scene,
+        prepared_ui: &PreparedUi,
+        viewport_extent: Extent2D,
+    ) -> Result<(), RendererError> {
+        if self.is_headless() {
+            return Err(RendererError::Headless);
+        }
+
+        // texture updates are only handed over once, so they're applied even
+        // if this frame ends up being skipped
+        for (id, delta) in &prepared_ui.textures_delta.set {
+            self.egui_renderer
+                .update_texture(&self.device, &self.queue, *id, delta);
+        }
+
+        let start = Instant::now();
+        let frame = self.acquire_frame();
+        self.record_pass("acquire", start, 0);
+
+        let Some(frame) = frame else {
+            for id in &prepared_ui.textures_delta.free {
+                self.egui_renderer.free_texture(id);
+            }
+
+            self.end_frame_draw_calls();
+            return Ok(());
+        };
+
+        let start = Instant::now();
+
+        let frame_view = frame.texture.create_view(&wgpu::TextureViewDescriptor {
+            format: Some(self.ui_format),
+            ..Default::default()
+        });
+
+        let mut encoder = self.device.create_command_encoder(&Default::default());
+
+        encoder.push_debug_group("ui uploads");
+        self.egui_renderer.update_buffers(
+            &self.device,
+            &self.queue,
+            &mut encoder,
             &prepared_ui.shapes,
             &egui_wgpu::ScreenDescriptor {
                 size_in_pixels: [viewport_extent.width, viewport_extent.height],
-                pixels_per_point: 1.0,
+                pixels_per_point: prepared_ui.pixels_per_point,
             },
         );
+        encoder.pop_debug_group();
+
+        let views = self
+            .views_pipeline
+            .as_ref()
+            .zip(self.views_target.as_ref())
+            .filter(|_| self.views_pending);
+        self.views_pending = false;
+
+        if let Some(((_, pipeline), (_, bind_group))) = views {
+            // written to through the sRGB view, unlike the UI
+            let srgb_view = frame.texture.create_view(&Default::default());
 
-        {
             let mut rp = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                label: None,
+                label: Some("views"),
                 color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &frame_view,
+                    view: &srgb_view,
                     resolve_target: None,
                     ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(wgpu::Color {
-                            r: (scene.bg_color >> 24 & 0xFF) as f64 / 255.0,
-                            g: (scene.bg_color >> 16 & 0xFF) as f64 / 255.0,
-                            b: (scene.bg_color >> 8 & 0xFF) as f64 / 255.0,
-                            a: (scene.bg_color & 0xFF) as f64 / 255.0,
-                        }),
+                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
                         store: wgpu::StoreOp::Store,
                     },
                 })],
                 depth_stencil_attachment: None,
                 timestamp_writes: None,
                 occlusion_query_set: None,
-            }).forget_lifetime();
+            });
+
+            rp.set_pipeline(pipeline);
+            rp.set_bind_group(0, bind_group, &[]);
+            draw_fullscreen_triangle(&mut rp);
+        }
+
+        let clear_color = {
+            let color = self.clear_color.unwrap_or(scene.bg_color());
+
+            // the UI view doesn't encode to sRGB, so the clear color is
+            // encoded here
+            let [r, g, b] = [color.r, color.g, color.b].map(linear_to_srgb);
+
+            let alpha = if self.alpha_mode() == wgpu::CompositeAlphaMode::PreMultiplied {
+                color.a
+            } else {
+                1.0
+            };
+
+            wgpu::Color {
+                r: (r * alpha) as f64,
+                g: (g * alpha) as f64,
+                b: (b * alpha) as f64,
+                a: color.a as f64,
+            }
+        };
+
+        {
+            let mut rp = encoder
+                .begin_render_pass(&wgpu::RenderPassDescriptor {
+                    label: Some("ui"),
+                    color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                        view: &frame_view,
+                        resolve_target: None,
+                        ops: wgpu::Operations {
+                            // views are only drawn over, not cleared
+                            load: if views.is_some() {
+                                wgpu::LoadOp::Load
+                            } else {
+                                wgpu::LoadOp::Clear(clear_color)
+                            },
+                            store: wgpu::StoreOp::Store,
+                        },
+                    })],
+                    depth_stencil_attachment: None,
+                    timestamp_writes: None,
+                    occlusion_query_set: None,
+                })
+                .forget_lifetime();
 
             self.egui_renderer.render(
                 &mut rp,
                 &prepared_ui.shapes,
                 &egui_wgpu::ScreenDescriptor {
                     size_in_pixels: [viewport_extent.width, viewport_extent.height],
-                    pixels_per_point: 1.0,
+                    pixels_per_point: prepared_ui.pixels_per_point,
                 },
             );
         }
@@ -420,8 +2187,362 @@ impl Renderer {
             self.egui_renderer.free_texture(id);
         }
 
+        let capture = self
+            .capture_requested
+            .then(|| self.copy_frame_for_capture(&mut encoder, &frame.texture))
+            .flatten();
+        self.capture_requested = false;
+
         self.queue.submit([encoder.finish()]);
 
+        // egui issues one draw per clipped primitive
+        self.record_pass("ui", start, prepared_ui.shapes.len() as u32);
+
+        if let Some((buffer, bytes_per_row)) = capture {
+            let data = self.read_mapped(&buffer);
+            self.capture = decode_capture(
+                &data,
+                self.surface_format,
+                frame.texture.width(),
+                frame.texture.height(),
+                bytes_per_row,
+            );
+        }
+
+        let suboptimal = frame.suboptimal;
+
+        let start = Instant::now();
         frame.present();
+        self.record_pass("present", start, 0);
+        self.end_frame_draw_calls();
+
+        self.frame_index = (self.frame_index + 1) % self.frames_in_flight;
+
+        if suboptimal {
+            self.configure_surface();
+        }
+
+        Ok(())
+    }
+
+    fn end_frame_draw_calls(&mut self) {
+        self.previous_frame_draw_calls = std::mem::take(&mut self.frame_draw_calls);
+    }
+
+    pub fn draw_calls(&self) -> u32 {
+        self.previous_frame_draw_calls
+    }
+
+    fn acquire_frame(&mut self) -> Option<wgpu::SurfaceTexture> {
+        let surface = self.surface.as_ref()?;
+
+        if self.surface_size.is_empty() {
+            return None;
+        }
+
+        match surface.get_current_texture() {
+            Ok(frame) => Some(frame),
+            Err(err @ (wgpu::SurfaceError::Outdated | wgpu::SurfaceError::Lost)) => {
+                warn!(%err, "recreating swapchain");
+                self.configure_surface();
+                None
+            }
+            Err(wgpu::SurfaceError::Timeout) => {
+                warn!("timed out acquiring the next frame, skipping it");
+                None
+            }
+            Err(wgpu::SurfaceError::OutOfMemory) => {
+                panic!("out of memory acquiring the next frame")
+            }
+        }
+    }
+
+    pub fn begin_profile(&mut self) {
+        self.pass_timings = Some(Vec::new());
+    }
+
+    pub fn end_profile(&mut self) -> Vec<PassTiming> {
+        self.pass_timings.take().unwrap_or_default()
+    }
+
+    fn record_pass(&mut self, name: &'static str, start: Instant, draw_calls: u32) {
+        self.frame_draw_calls += draw_calls;
+
+        if let Some(pass_timings) = &mut self.pass_timings {
+            pass_timings.push(PassTiming {
+                name,
+                duration: start.elapsed(),
+                draw_calls,
+            });
+        }
+    }
+
+    pub fn request_capture(&mut self) {
+        self.capture_requested = true;
+    }
+
+    pub fn take_capture(&mut self) -> Option<image::RgbaImage> {
+        self.capture.take()
+    }
+
+    fn copy_frame_for_capture(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        texture: &wgpu::Texture,
+    ) -> Option<(wgpu::Buffer, u32)> {
+        if !self.surface_usage.contains(wgpu::TextureUsages::COPY_SRC) {
+            warn!("surface doesn't support COPY_SRC, can't capture frame");
+            return None;
+        }
+
+        let bytes_per_row =
+            (texture.width() * 4).next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+
+        let buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("frame capture"),
+            size: bytes_per_row as u64 * texture.height() as u64,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        encoder.insert_debug_marker("frame capture");
+        encoder.copy_texture_to_buffer(
+            texture.as_image_copy(),
+            wgpu::ImageCopyBuffer {
+                buffer: &buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(bytes_per_row),
+                    rows_per_image: None,
+                },
+            },
+            texture.size(),
+        );
+
+        Some((buffer, bytes_per_row))
+    }
+}
+
+fn create_instance() -> wgpu::Instance {
+    wgpu::Instance::new(wgpu::InstanceDescriptor {
+        backends: wgpu::Backends::VULKAN,
+        // debug labels show up in captures, wgpu ignores them if the
+        // debug utils extension is missing
+        flags: if cfg!(debug_assertions) {
+            wgpu::InstanceFlags::DEBUG
+        } else {
+            wgpu::InstanceFlags::empty()
+        },
+        dx12_shader_compiler: wgpu::Dx12Compiler::Fxc,
+        gles_minor_version: wgpu::Gles3MinorVersion::Automatic,
+    })
+}
+
+fn overlay_depth_state() -> wgpu::DepthStencilState {
+    wgpu::DepthStencilState {
+        format: DEPTH_FORMAT,
+        depth_write_enabled: false,
+        depth_compare: wgpu::CompareFunction::Always,
+        stencil: wgpu::StencilState::default(),
+        bias: wgpu::DepthBiasState::default(),
+    }
+}
+
+fn set_view_rect(rp: &mut wgpu::RenderPass, rect: ViewRect) {
+    let [x, y] = rect.origin;
+    let Extent2D { width, height } = rect.size;
+
+    rp.set_viewport(x as f32, y as f32, width as f32, height as f32, 0.0, 1.0);
+    rp.set_scissor_rect(x, y, width, height);
+}
+
+fn draw_fullscreen_triangle(rp: &mut wgpu::RenderPass) {
+    rp.draw(0..3, 0..1);
+}
+
+fn mip_level_count(width: u32, height: u32) -> u32 {
+    u32::BITS - width.max(height).max(1).leading_zeros()
+}
+
+fn srgb_image_to_linear(image: &image::RgbaImage) -> image::Rgba32FImage {
+    let (width, height) = image.dimensions();
+
+    image::Rgba32FImage::from_fn(width, height, |x, y| {
+        image::Rgba(Color::from_srgba_u8(image.get_pixel(x, y).0).to_array())
+    })
+}
+
+fn linear_image_to_srgb(image: &image::Rgba32FImage) -> image::RgbaImage {
+    let (width, height) = image.dimensions();
+
+    image::RgbaImage::from_fn(width, height, |x, y| {
+        image::Rgba(Color::from_array(image.get_pixel(x, y).0).to_srgba_u8())
+    })
+}
+
+fn downsample(image: &image::Rgba32FImage) -> image::Rgba32FImage {
+    let (width, height) = image.dimensions();
+
+    image::imageops::resize(
+        image,
+        (width / 2).max(1),
+        (height / 2).max(1),
+        image::imageops::FilterType::Triangle,
+    )
+}
+
+fn decode_capture(
+    data: &[u8],
+    format: wgpu::TextureFormat,
+    width: u32,
+    height: u32,
+    bytes_per_row: u32,
+) -> Option<image::RgbaImage> {
+    let bgra = match format {
+        wgpu::TextureFormat::Rgba8Unorm | wgpu::TextureFormat::Rgba8UnormSrgb => false,
+        wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb => true,
+        _ => {
+            warn!(?format, "can't capture frame with this surface format");
+            return None;
+        }
+    };
+
+    let mut pixels = Vec::with_capacity(width as usize * height as usize * 4);
+
+    for row in data.chunks(bytes_per_row as usize).take(height as usize) {
+        for pixel in row[..width as usize * 4].chunks_exact(4) {
+            let (r, g, b) = if bgra {
+                (pixel[2], pixel[1], pixel[0])
+            } else {
+                (pixel[0], pixel[1], pixel[2])
+            };
+
+            pixels.extend_from_slice(&[r, g, b, 0xFF]);
+        }
+    }
+
+    image::RgbaImage::from_raw(width, height, pixels)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_extent_aspect_ratio() {
+        let extent = Extent2D {
+            width: 800,
+            height: 0,
+        };
+
+        assert!(extent.is_empty());
+        assert_eq!(extent.aspect_ratio(), 1.0);
+    }
+
+    #[test]
+    fn split_views_share_edges() {
+        let camera = Camera::new();
+        let scene = Scene::new();
+        let target = Extent2D {
+            width: 101,
+            height: 50,
+        };
+
+        let view = |origin, size| ViewDesc {
+            camera: &camera,
+            root: scene.root(),
+            origin,
+            size,
+        };
+
+        let left = view(Vec2::ZERO, Vec2::new(0.5, 1.0)).pixel_rect(target);
+        let right = view(Vec2::new(0.5, 0.0), Vec2::new(0.5, 1.0)).pixel_rect(target);
+
+        assert_eq!(left.origin[0] + left.size.width, right.origin[0]);
+        assert_eq!(right.origin[0] + right.size.width, target.width);
+        assert_eq!(left.size.height, target.height);
+    }
+
+    #[test]
+    fn full_mip_chain() {
+        assert_eq!(mip_level_count(1, 1), 1);
+        assert_eq!(mip_level_count(256, 256), 9);
+        assert_eq!(mip_level_count(300, 20), 9);
+        assert_eq!(mip_level_count(1, 1024), 11);
+    }
+
+    #[test]
+    fn mips_are_filtered_in_linear_space() {
+        let image =
+            image::RgbaImage::from_raw(2, 1, vec![0, 0, 0, 255, 255, 255, 255, 255]).unwrap();
+
+        let mip = linear_image_to_srgb(&downsample(&srgb_image_to_linear(&image)));
+
+        // half as bright as white, rather than the 128 of averaging in sRGB
+        assert_eq!(mip.get_pixel(0, 0).0, [188, 188, 188, 255]);
+    }
+
+    #[test]
+    fn decode_capture_bgra() {
+        // 2x2 image, rows padded to 12 bytes
+        let data = [
+            1, 2, 3, 0, 4, 5, 6, 0, 0xAA, 0xAA, 0xAA, 0xAA, //
+            7, 8, 9, 0, 10, 11, 12, 0, 0xAA, 0xAA, 0xAA, 0xAA,
+        ];
+
+        let image = decode_capture(&data, wgpu::TextureFormat::Bgra8UnormSrgb, 2, 2, 12).unwrap();
+
+        assert_eq!(
+            image.into_raw(),
+            [3, 2, 1, 255, 6, 5, 4, 255, 9, 8, 7, 255, 12, 11, 10, 255]
+        );
+    }
+
+    #[test]
+    fn decode_capture_rgba() {
+        let data = [1, 2, 3, 4, 5, 6, 7, 8];
+
+        let image = decode_capture(&data, wgpu::TextureFormat::Rgba8Unorm, 2, 1, 8).unwrap();
+
+        assert_eq!(image.into_raw(), [1, 2, 3, 255, 5, 6, 7, 255]);
+    }
+
+    #[test]
+    #[ignore = "needs a GPU and dxcompiler"]
+    fn headless_readback() {
+        use crate::loader::{ShaderBytecode, ShaderCompiler};
+
+        let compiler = ShaderCompiler::new();
+        let compile = |stage| compiler.compile_error_shader(stage, ShaderBytecode::SpirV);
+
+        let mut renderer =
+            Renderer::headless(compile(ShaderStage::Vertex), compile(ShaderStage::Fragment));
+
+        let buffer = renderer.device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: 16,
+            usage: wgpu::BufferUsages::COPY_SRC | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let data: Vec<u8> = (0..16).collect();
+        renderer.queue.write_buffer(&buffer, 0, &data);
+
+        assert_eq!(renderer.read_buffer(&buffer), data);
+
+        let size = Extent2D {
+            width: 4,
+            height: 4,
+        };
+        let scene = Scene::new();
+        let target = renderer.create_egui_render_target(size);
+        renderer.render_scene_to_egui_texture(target, size, &scene, scene.root(), &Camera::new());
+
+        // nothing was drawn, so the background is picked
+        assert_eq!(renderer.pick_object(target, 1, 1), Some(Pick::Background));
+
+        assert!(matches!(
+            renderer.render(&scene, &PreparedUi::default(), size),
+            Err(RendererError::Headless)
+        ));
     }
 }
@@ -0,0 +1,125 @@
+use std::num::NonZeroU64;
+
+use glam::Mat4;
+
+const INITIAL_CAPACITY: u32 = 64;
+
+/// `register(b0, space2)` in HLSL.
+#[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+#[repr(C)]
+pub(super) struct ObjectUniforms {
+    pub transform: Mat4,
+    /// Node index + 1, 0 is the background.
+    pub object_id: u32,
+    pub _padding: [u32; 3],
+}
+
+pub(super) struct ObjectBuffer {
+    layout: wgpu::BindGroupLayout,
+    buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+    stride: u64,
+    capacity: u32,
+}
+
+impl ObjectBuffer {
+    pub fn new(device: &wgpu::Device) -> Self {
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("object"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: true,
+                    min_binding_size: NonZeroU64::new(size_of::<ObjectUniforms>() as u64),
+                },
+                count: None,
+            }],
+        });
+
+        let alignment = device.limits().min_uniform_buffer_offset_alignment as u64;
+        let stride = (size_of::<ObjectUniforms>() as u64).next_multiple_of(alignment);
+
+        let (buffer, bind_group) = create_buffer(device, &layout, stride, INITIAL_CAPACITY);
+
+        Self {
+            layout,
+            buffer,
+            bind_group,
+            stride,
+            capacity: INITIAL_CAPACITY,
+        }
+    }
+
+    pub fn layout(&self) -> &wgpu::BindGroupLayout {
+        &self.layout
+    }
+
+    pub fn bind_group(&self) -> &wgpu::BindGroup {
+        &self.bind_group
+    }
+
+    pub fn offset(&self, index: u32) -> u32 {
+        (index as u64 * self.stride) as u32
+    }
+
+    pub fn write(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        objects: &[ObjectUniforms],
+    ) {
+        if objects.is_empty() {
+            return;
+        }
+
+        let count = objects.len() as u32;
+
+        if count > self.capacity {
+            self.capacity = count.next_power_of_two();
+            (self.buffer, self.bind_group) =
+                create_buffer(device, &self.layout, self.stride, self.capacity);
+        }
+
+        let mut data = vec![0; objects.len() * self.stride as usize];
+
+        for (object, chunk) in objects
+            .iter()
+            .zip(data.chunks_exact_mut(self.stride as usize))
+        {
+            chunk[..size_of::<ObjectUniforms>()].copy_from_slice(bytemuck::bytes_of(object));
+        }
+
+        queue.write_buffer(&self.buffer, 0, &data);
+    }
+}
+
+fn create_buffer(
+    device: &wgpu::Device,
+    layout: &wgpu::BindGroupLayout,
+    stride: u64,
+    capacity: u32,
+) -> (wgpu::Buffer, wgpu::BindGroup) {
+    let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("object"),
+        size: stride * capacity as u64,
+        usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+
+    let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("object"),
+        layout,
+        entries: &[wgpu::BindGroupEntry {
+            binding: 0,
+            resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                buffer: &buffer,
+                offset: 0,
+                size: NonZeroU64::new(size_of::<ObjectUniforms>() as u64),
+            }),
+        }],
+    });
+
+    (buffer, bind_group)
+}
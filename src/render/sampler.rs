@@ -0,0 +1,78 @@
+use tracing::warn;
+
+/// Highest anisotropy wgpu accepts.
+pub const MAX_ANISOTROPY: u16 = 16;
+
+/// How a texture is filtered and addressed, independently of the texture
+/// itself.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SamplerDesc {
+    pub min_filter: wgpu::FilterMode,
+    pub mag_filter: wgpu::FilterMode,
+    pub mipmap_mode: wgpu::FilterMode,
+    pub address_u: wgpu::AddressMode,
+    pub address_v: wgpu::AddressMode,
+    pub address_w: wgpu::AddressMode,
+    /// Maximum anisotropy, 1 disables anisotropic filtering. Anything above 1
+    /// requires all filters to be linear. It's further limited by
+    /// [`Renderer::anisotropy`](super::Renderer::anisotropy).
+    pub anisotropy: u16,
+}
+
+impl SamplerDesc {
+    pub const LINEAR_REPEAT: SamplerDesc = SamplerDesc {
+        min_filter: wgpu::FilterMode::Linear,
+        mag_filter: wgpu::FilterMode::Linear,
+        mipmap_mode: wgpu::FilterMode::Linear,
+        address_u: wgpu::AddressMode::Repeat,
+        address_v: wgpu::AddressMode::Repeat,
+        address_w: wgpu::AddressMode::Repeat,
+        anisotropy: MAX_ANISOTROPY,
+    };
+
+    pub const NEAREST_CLAMP: SamplerDesc = SamplerDesc {
+        min_filter: wgpu::FilterMode::Nearest,
+        mag_filter: wgpu::FilterMode::Nearest,
+        mipmap_mode: wgpu::FilterMode::Nearest,
+        address_u: wgpu::AddressMode::ClampToEdge,
+        address_v: wgpu::AddressMode::ClampToEdge,
+        address_w: wgpu::AddressMode::ClampToEdge,
+        anisotropy: 1,
+    };
+
+    pub(super) fn to_wgpu(self, max_anisotropy: u16) -> wgpu::SamplerDescriptor<'static> {
+        let all_linear = [self.min_filter, self.mag_filter, self.mipmap_mode]
+            .iter()
+            .all(|filter| *filter == wgpu::FilterMode::Linear);
+
+        let anisotropy = self.anisotropy.min(max_anisotropy);
+
+        let anisotropy = if anisotropy > 1 && !all_linear {
+            warn!(
+                anisotropy = self.anisotropy,
+                "anisotropic filtering needs linear filters, disabling it"
+            );
+            1
+        } else {
+            anisotropy.max(1)
+        };
+
+        wgpu::SamplerDescriptor {
+            label: None,
+            address_mode_u: self.address_u,
+            address_mode_v: self.address_v,
+            address_mode_w: self.address_w,
+            mag_filter: self.mag_filter,
+            min_filter: self.min_filter,
+            mipmap_filter: self.mipmap_mode,
+            anisotropy_clamp: anisotropy,
+            ..Default::default()
+        }
+    }
+}
+
+impl Default for SamplerDesc {
+    fn default() -> Self {
+        Self::LINEAR_REPEAT
+    }
+}
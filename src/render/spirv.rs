@@ -0,0 +1,325 @@
+//! Just enough SPIR-V parsing to check vertex shader inputs against a vertex
+//! buffer layout. Shaders are passed through to the driver, so wgpu doesn't
+//! check them itself.
+
+use std::fmt;
+
+use ahash::AHashMap;
+
+const MAGIC: u32 = 0x0723_0203;
+const HEADER_WORDS: usize = 5;
+
+const OP_ENTRY_POINT: u32 = 15;
+const OP_TYPE_INT: u32 = 21;
+const OP_TYPE_FLOAT: u32 = 22;
+const OP_TYPE_VECTOR: u32 = 23;
+const OP_TYPE_POINTER: u32 = 32;
+const OP_VARIABLE: u32 = 59;
+const OP_DECORATE: u32 = 71;
+
+const EXECUTION_MODEL_VERTEX: u32 = 0;
+const STORAGE_CLASS_INPUT: u32 = 1;
+const DECORATION_LOCATION: u32 = 30;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScalarKind {
+    Float,
+    Sint,
+    Uint,
+}
+
+/// Type of a vertex shader input, as far as vertex formats care.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InputType {
+    pub kind: ScalarKind,
+    pub components: u32,
+}
+
+impl InputType {
+    /// What a shader reads an attribute of `format` as, `None` for formats
+    /// that aren't checked.
+    fn of_format(format: wgpu::VertexFormat) -> Option<Self> {
+        use wgpu::VertexFormat as F;
+
+        let (kind, components) = match format {
+            F::Float32 => (ScalarKind::Float, 1),
+            F::Float32x2 | F::Float16x2 | F::Unorm8x2 | F::Snorm8x2 => (ScalarKind::Float, 2),
+            F::Unorm16x2 | F::Snorm16x2 => (ScalarKind::Float, 2),
+            F::Float32x3 => (ScalarKind::Float, 3),
+            F::Float32x4 | F::Float16x4 | F::Unorm8x4 | F::Snorm8x4 => (ScalarKind::Float, 4),
+            F::Unorm16x4 | F::Snorm16x4 => (ScalarKind::Float, 4),
+            F::Uint32 => (ScalarKind::Uint, 1),
+            F::Uint32x2 | F::Uint8x2 | F::Uint16x2 => (ScalarKind::Uint, 2),
+            F::Uint32x3 => (ScalarKind::Uint, 3),
+            F::Uint32x4 | F::Uint8x4 | F::Uint16x4 => (ScalarKind::Uint, 4),
+            F::Sint32 => (ScalarKind::Sint, 1),
+            F::Sint32x2 | F::Sint8x2 | F::Sint16x2 => (ScalarKind::Sint, 2),
+            F::Sint32x3 => (ScalarKind::Sint, 3),
+            F::Sint32x4 | F::Sint8x4 | F::Sint16x4 => (ScalarKind::Sint, 4),
+            _ => return None,
+        };
+
+        Some(Self { kind, components })
+    }
+}
+
+impl fmt::Display for InputType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let kind = match self.kind {
+            ScalarKind::Float => "float",
+            ScalarKind::Sint => "int",
+            ScalarKind::Uint => "uint",
+        };
+
+        match self.components {
+            1 => write!(f, "{kind}"),
+            n => write!(f, "{kind}{n}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VertexInputError {
+    Missing {
+        location: u32,
+    },
+    Mismatch {
+        location: u32,
+        shader: InputType,
+        layout: wgpu::VertexFormat,
+    },
+}
+
+/// Checks that every input of the vertex entry point `entry_point` has an
+/// attribute of a matching format in `layout`. Attributes the shader doesn't
+/// read are fine. Modules that can't be parsed pass, they fail later on
+/// their own.
+pub fn check_vertex_inputs(
+    spirv: &[u8],
+    entry_point: &str,
+    layout: &wgpu::VertexBufferLayout,
+) -> Result<(), VertexInputError> {
+    let words: Vec<u32> = spirv
+        .chunks_exact(4)
+        .map(|word| u32::from_le_bytes(word.try_into().unwrap()))
+        .collect();
+
+    let Some(inputs) = vertex_inputs(&words, entry_point) else {
+        return Ok(());
+    };
+
+    for (location, shader) in inputs {
+        let attribute = layout
+            .attributes
+            .iter()
+            .find(|attribute| attribute.shader_location == location)
+            .ok_or(VertexInputError::Missing { location })?;
+
+        let matches = InputType::of_format(attribute.format).is_none_or(|layout| layout == shader);
+
+        if !matches {
+            return Err(VertexInputError::Mismatch {
+                location,
+                shader,
+                layout: attribute.format,
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// Locations and types of the inputs of a vertex entry point. Inputs without
+/// a location, i.e. builtins, and of types other than scalars and vectors
+/// are left out.
+fn vertex_inputs(words: &[u32], entry_point: &str) -> Option<Vec<(u32, InputType)>> {
+    if words.len() < HEADER_WORDS || words[0] != MAGIC {
+        return None;
+    }
+
+    let mut interface = None;
+    let mut locations = AHashMap::new();
+    let mut scalars = AHashMap::new();
+    let mut types = AHashMap::new();
+    let mut pointers = AHashMap::new();
+    let mut input_variables = AHashMap::new();
+
+    let mut rest = &words[HEADER_WORDS..];
+
+    while let Some(&first) = rest.first() {
+        let word_count = (first >> 16) as usize;
+
+        if word_count == 0 || word_count > rest.len() {
+            return None;
+        }
+
+        let (instruction, next) = rest.split_at(word_count);
+        let operands = &instruction[1..];
+        rest = next;
+
+        match (first & 0xFFFF, operands) {
+            (OP_ENTRY_POINT, [EXECUTION_MODEL_VERTEX, _, name_and_interface @ ..]) => {
+                let (name, ids) = split_string(name_and_interface)?;
+
+                if name == entry_point {
+                    interface = Some(ids.to_vec());
+                }
+            }
+            (OP_DECORATE, &[target, DECORATION_LOCATION, location]) => {
+                locations.insert(target, location);
+            }
+            (OP_TYPE_FLOAT, &[id, _]) => {
+                scalars.insert(id, ScalarKind::Float);
+            }
+            (OP_TYPE_INT, &[id, _, signed]) => {
+                let kind = if signed == 0 {
+                    ScalarKind::Uint
+                } else {
+                    ScalarKind::Sint
+                };
+                scalars.insert(id, kind);
+            }
+            (OP_TYPE_VECTOR, &[id, component, count]) => {
+                types.insert(id, (component, count));
+            }
+            (OP_TYPE_POINTER, &[id, STORAGE_CLASS_INPUT, pointee]) => {
+                pointers.insert(id, pointee);
+            }
+            (OP_VARIABLE, &[pointer, id, STORAGE_CLASS_INPUT, ..]) => {
+                input_variables.insert(id, pointer);
+            }
+            _ => {}
+        }
+    }
+
+    let input_type = |type_id| {
+        let (scalar, components) = types.get(&type_id).copied().unwrap_or((type_id, 1));
+
+        Some(InputType {
+            kind: *scalars.get(&scalar)?,
+            components,
+        })
+    };
+
+    let inputs = interface?
+        .into_iter()
+        .filter_map(|id| {
+            let pointee = pointers.get(input_variables.get(&id)?)?;
+            Some((*locations.get(&id)?, input_type(*pointee)?))
+        })
+        .collect();
+
+    Some(inputs)
+}
+
+/// Splits a null-terminated string literal off the front of `words`.
+fn split_string(words: &[u32]) -> Option<(String, &[u32])> {
+    let mut bytes = Vec::new();
+
+    for (index, word) in words.iter().enumerate() {
+        for byte in word.to_le_bytes() {
+            if byte == 0 {
+                let name = String::from_utf8(bytes).ok()?;
+                return Some((name, &words[index + 1..]));
+            }
+
+            bytes.push(byte);
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn instruction(opcode: u32, operands: &[u32]) -> Vec<u32> {
+        let mut words = vec![((operands.len() as u32 + 1) << 16) | opcode];
+        words.extend_from_slice(operands);
+        words
+    }
+
+    /// Module with a `vs_main` vertex entry point reading a float3 at
+    /// location 0 and a builtin vertex index.
+    fn vertex_module() -> Vec<u8> {
+        let name = u32::from_le_bytes(*b"vs_m");
+        let name_end = u32::from_le_bytes(*b"ain\0");
+
+        let (float, float3, pointer, uint, uint_pointer) = (1, 2, 3, 4, 5);
+        let (position, vertex_id, function) = (10, 11, 12);
+
+        let words: Vec<u32> = [
+            vec![MAGIC, 0x0001_0000, 0, 20, 0],
+            instruction(
+                OP_ENTRY_POINT,
+                &[0, function, name, name_end, position, vertex_id],
+            ),
+            instruction(OP_DECORATE, &[position, DECORATION_LOCATION, 0]),
+            // BuiltIn VertexIndex
+            instruction(OP_DECORATE, &[vertex_id, 11, 42]),
+            instruction(OP_TYPE_FLOAT, &[float, 32]),
+            instruction(OP_TYPE_VECTOR, &[float3, float, 3]),
+            instruction(OP_TYPE_POINTER, &[pointer, STORAGE_CLASS_INPUT, float3]),
+            instruction(OP_TYPE_INT, &[uint, 32, 0]),
+            instruction(OP_TYPE_POINTER, &[uint_pointer, STORAGE_CLASS_INPUT, uint]),
+            instruction(OP_VARIABLE, &[pointer, position, STORAGE_CLASS_INPUT]),
+            instruction(OP_VARIABLE, &[uint_pointer, vertex_id, STORAGE_CLASS_INPUT]),
+        ]
+        .concat();
+
+        words.iter().flat_map(|word| word.to_le_bytes()).collect()
+    }
+
+    fn layout(attributes: &[wgpu::VertexAttribute]) -> wgpu::VertexBufferLayout<'_> {
+        wgpu::VertexBufferLayout {
+            array_stride: 0,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes,
+        }
+    }
+
+    fn attribute(format: wgpu::VertexFormat, shader_location: u32) -> wgpu::VertexAttribute {
+        wgpu::VertexAttribute {
+            format,
+            offset: 0,
+            shader_location,
+        }
+    }
+
+    #[test]
+    fn matching_layout_passes() {
+        let attributes = [
+            attribute(wgpu::VertexFormat::Float32x3, 0),
+            attribute(wgpu::VertexFormat::Float32x2, 1),
+        ];
+
+        assert_eq!(
+            check_vertex_inputs(&vertex_module(), "vs_main", &layout(&attributes)),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn missing_and_mismatched_inputs() {
+        let moved = [attribute(wgpu::VertexFormat::Float32x3, 1)];
+        let wrong_format = [attribute(wgpu::VertexFormat::Uint32x3, 0)];
+
+        assert_eq!(
+            check_vertex_inputs(&vertex_module(), "vs_main", &layout(&moved)),
+            Err(VertexInputError::Missing { location: 0 })
+        );
+        assert!(matches!(
+            check_vertex_inputs(&vertex_module(), "vs_main", &layout(&wrong_format)),
+            Err(VertexInputError::Mismatch { location: 0, .. })
+        ));
+    }
+
+    #[test]
+    fn other_entry_points_are_ignored() {
+        assert_eq!(
+            check_vertex_inputs(&vertex_module(), "fs_main", &layout(&[])),
+            Ok(())
+        );
+    }
+}
@@ -1,7 +1,11 @@
 use glam::{vec3, Mat4, Quat, Vec3};
 
+use crate::math::Aabb;
 use crate::scene::Node;
 
+const NEAR: f32 = 0.1;
+const FAR: f32 = 2000.0;
+
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct Camera {
     pub position: Vec3,
@@ -48,16 +52,60 @@ impl Camera {
         (look, right)
     }
 
-    pub fn view_projection(&self, aspect_ratio: f32) -> Mat4 {
-        let projection = Mat4::perspective_rh(self.fov.to_radians(), aspect_ratio, 0.1, 2000.0);
+    /// Moves the camera back along its view direction until `bounds` fit in
+    /// the vertical field of view.
+    pub fn frame(&mut self, bounds: Aabb) {
+        let (look, _) = self.forward_right();
+
+        let radius = bounds.half_extents().length();
+        let distance = radius / (self.fov.to_radians() * 0.5).sin();
+
+        self.position = bounds.center() - look * distance;
+    }
+
+    /// Right-handed perspective projection with a vertical `fov` in degrees
+    /// and depth mapped to `0..1`.
+    pub fn perspective(fov: f32, aspect_ratio: f32, near: f32, far: f32) -> Mat4 {
+        Mat4::perspective_rh(fov.to_radians(), aspect_ratio, near, far)
+    }
+
+    /// Right-handed orthographic projection of a box `height` units tall,
+    /// centered on the view direction, with depth mapped to `0..1`.
+    pub fn orthographic(height: f32, aspect_ratio: f32, near: f32, far: f32) -> Mat4 {
+        let half_height = height * 0.5;
+        let half_width = half_height * aspect_ratio;
+
+        Mat4::orthographic_rh(
+            -half_width,
+            half_width,
+            -half_height,
+            half_height,
+            near,
+            far,
+        )
+    }
+
+    pub fn projection(&self, aspect_ratio: f32) -> Mat4 {
+        Self::perspective(self.fov, aspect_ratio, NEAR, FAR)
+    }
+
+    /// Camera-to-world transform, the inverse of [`Camera::view_matrix`].
+    pub fn world_transform(&self) -> Mat4 {
+        Mat4::from_rotation_translation(self.rotation(), self.position)
+    }
 
+    pub fn view_matrix(&self) -> Mat4 {
         // world should rotate inversely to camera rotation
         let world_rotation = Mat4::from_quat(self.rotation().inverse());
 
         // world should be shifted away from the camera
         let world_translation = Mat4::from_translation(-self.position);
 
-        projection * world_rotation * world_translation
+        world_rotation * world_translation
+    }
+
+    pub fn view_projection(&self, aspect_ratio: f32) -> Mat4 {
+        self.projection(aspect_ratio) * self.view_matrix()
     }
 }
 
@@ -66,3 +114,29 @@ impl From<Camera> for Node {
         Node::Camera(value)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn world_transform_at_origin_is_identity() {
+        let camera = Camera {
+            position: Vec3::ZERO,
+            ..Camera::new()
+        };
+
+        assert!(camera.world_transform().abs_diff_eq(Mat4::IDENTITY, 1e-6));
+    }
+
+    #[test]
+    fn world_transform_inverts_view() {
+        let mut camera = Camera::new();
+        camera.position = vec3(1.0, 2.0, 3.0);
+        camera.rotate(30.0, 45.0);
+
+        let product = camera.world_transform() * camera.view_matrix();
+
+        assert!(product.abs_diff_eq(Mat4::IDENTITY, 1e-5));
+    }
+}
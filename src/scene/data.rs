@@ -0,0 +1,225 @@
+use std::path::Path;
+
+use ahash::AHashMap;
+
+use crate::color::Color;
+use crate::core::Arena;
+use crate::scene::{Lighting, Node, NodeHandle, Scene, Spatial, Transform};
+
+/// Serializable snapshot of a [`Scene`]. Nodes refer to each other by their
+/// index in `nodes`.
+///
+/// The environment map is a GPU resource and isn't part of the snapshot.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct SceneData {
+    pub bg_color: Color,
+    pub lighting: Lighting,
+    pub root: usize,
+    pub primary_camera: Option<usize>,
+    pub nodes: Vec<NodeData>,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct NodeData {
+    pub transform: Transform,
+    pub visible: bool,
+    pub enabled: bool,
+    pub children: Vec<usize>,
+    pub node: Node,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum SceneFileError {
+    #[error("failed to read scene file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("failed to parse scene file: {0}")]
+    Parse(#[from] serde_json::Error),
+    #[error("node {index} refers to missing node {target}")]
+    MissingNode { index: usize, target: usize },
+    #[error("scene has no node {0}")]
+    InvalidNodeIndex(usize),
+    #[error("node {0} has more than one parent")]
+    MultipleParents(usize),
+    #[error("node {0} is its own ancestor")]
+    Cycle(usize),
+    #[error("root node {0} has a parent")]
+    RootHasParent(usize),
+}
+
+impl SceneData {
+    /// Checks that nodes only refer to nodes that exist and form a tree,
+    /// which [`Scene::from_data`] relies on.
+    pub fn validate(&self) -> Result<(), SceneFileError> {
+        let len = self.nodes.len();
+
+        for index in std::iter::once(self.root).chain(self.primary_camera) {
+            if index >= len {
+                return Err(SceneFileError::InvalidNodeIndex(index));
+            }
+        }
+
+        for (index, node) in self.nodes.iter().enumerate() {
+            if let Some(&target) = node.children.iter().find(|&&child| child >= len) {
+                return Err(SceneFileError::MissingNode { index, target });
+            }
+        }
+
+        let mut has_parent = vec![false; len];
+
+        for &child in self.nodes.iter().flat_map(|node| &node.children) {
+            if std::mem::replace(&mut has_parent[child], true) {
+                return Err(SceneFileError::MultipleParents(child));
+            }
+        }
+
+        // with one parent per node, whatever can't be reached from a node
+        // without a parent is part of a cycle
+        let mut visited = vec![false; len];
+        let mut stack: Vec<usize> = (0..len).filter(|&index| !has_parent[index]).collect();
+
+        while let Some(index) = stack.pop() {
+            visited[index] = true;
+            stack.extend(&self.nodes[index].children);
+        }
+
+        if let Some(index) = visited.iter().position(|visited| !visited) {
+            return Err(SceneFileError::Cycle(index));
+        }
+
+        if has_parent[self.root] {
+            return Err(SceneFileError::RootHasParent(self.root));
+        }
+
+        Ok(())
+    }
+}
+
+impl Scene {
+    /// Reads a scene saved as JSON [`SceneData`].
+    pub fn load(path: impl AsRef<Path>) -> Result<Scene, SceneFileError> {
+        let data: SceneData = serde_json::from_slice(&std::fs::read(path)?)?;
+        data.validate()?;
+
+        Ok(Scene::from_data(data))
+    }
+
+    pub fn to_data(&self) -> SceneData {
+        let indices: AHashMap<NodeHandle, usize> = self
+            .nodes
+            .iter()
+            .enumerate()
+            .map(|(index, (handle, _))| (handle, index))
+            .collect();
+
+        let nodes = self
+            .nodes
+            .iter()
+            .map(|(_, spatial)| NodeData {
+                transform: spatial.transform,
+                visible: spatial.visible,
+                enabled: spatial.enabled,
+                children: spatial
+                    .children
+                    .iter()
+                    .map(|child| indices[child])
+                    .collect(),
+                node: spatial.node.clone(),
+            })
+            .collect();
+
+        SceneData {
+            bg_color: self.bg_color,
+            lighting: self.lighting,
+            root: indices[&self.root_node],
+            primary_camera: self.primary_camera_id.map(|id| indices[&id]),
+            nodes,
+        }
+    }
+
+    pub fn from_data(data: SceneData) -> Scene {
+        let mut nodes = Arena::new();
+
+        let handles: Vec<NodeHandle> = data
+            .nodes
+            .iter()
+            .map(|node| {
+                nodes.insert(
+                    Spatial::new(node.node.clone())
+                        .with_transform(node.transform)
+                        .with_visible(node.visible)
+                        .with_enabled(node.enabled),
+                )
+            })
+            .collect();
+
+        for (index, node) in data.nodes.iter().enumerate() {
+            let parent = handles[index];
+
+            for &child in &node.children {
+                nodes[parent].children.push(handles[child]);
+                nodes[handles[child]].parent = Some(parent);
+            }
+        }
+
+        let mut scene = Scene {
+            bg_color: data.bg_color,
+            lighting: data.lighting,
+            environment: None,
+            primary_camera_id: data.primary_camera.map(|index| handles[index]),
+            nodes,
+            root_node: handles[data.root],
+        };
+
+        scene.update_transform_hierarchy();
+        scene
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scene::Pivot;
+
+    #[test]
+    fn validate_rejects_missing_children() {
+        let mut data = Scene::new().to_data();
+        assert!(data.validate().is_ok());
+
+        data.nodes[data.root].children.push(5);
+        assert!(matches!(
+            data.validate(),
+            Err(SceneFileError::MissingNode { target: 5, .. })
+        ));
+    }
+
+    #[test]
+    fn validate_rejects_cycles() {
+        let mut data = Scene::new().to_data();
+        let root = data.root;
+
+        data.nodes[root].children.push(root);
+        assert!(matches!(data.validate(), Err(SceneFileError::Cycle(index)) if index == root));
+    }
+
+    #[test]
+    fn validate_rejects_multiple_parents() {
+        let mut scene = Scene::new();
+
+        for _ in 0..2 {
+            let pivot = scene.add_node(Spatial::new(Pivot::new()));
+            scene.link(scene.root(), pivot);
+        }
+
+        let mut data = scene.to_data();
+        assert!(data.validate().is_ok());
+
+        let [first, second] = data.nodes[data.root].children[..] else {
+            unreachable!()
+        };
+        data.nodes[first].children.push(second);
+        assert!(matches!(
+            data.validate(),
+            Err(SceneFileError::MultipleParents(index)) if index == second
+        ));
+    }
+}
@@ -0,0 +1,127 @@
+use glam::{Quat, Vec2, Vec3};
+use winit::keyboard::KeyCode;
+
+use crate::input::InputState;
+use crate::scene::Camera;
+
+/// Just short of straight up or down, past which yaw would flip.
+const MAX_PITCH: f32 = 89.0;
+
+/// First-person camera turned by mouse motion and moved relative to where
+/// it's facing. Angles follow [`Camera`], so [`FreeLookCamera::camera`] is a
+/// plain copy.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct FreeLookCamera {
+    pub position: Vec3,
+    /// Degrees, positive looks up. Kept within ±89°.
+    pitch: f32,
+    /// Degrees, positive turns right.
+    yaw: f32,
+    pub fov: f32,
+    /// Degrees per unit of mouse motion.
+    pub sensitivity: f32,
+    /// Units per second.
+    pub speed: f32,
+}
+
+impl FreeLookCamera {
+    pub fn new(position: Vec3) -> Self {
+        Self {
+            position,
+            pitch: 0.0,
+            yaw: 0.0,
+            fov: 75.0,
+            sensitivity: 0.1,
+            speed: 5.0,
+        }
+    }
+
+    pub fn pitch(&self) -> f32 {
+        self.pitch
+    }
+
+    pub fn yaw(&self) -> f32 {
+        self.yaw
+    }
+
+    /// Turns by a mouse motion of `delta`, where positive `y` is down.
+    pub fn look(&mut self, delta: Vec2) {
+        self.yaw = (self.yaw + delta.x * self.sensitivity) % 360.0;
+        self.pitch = (self.pitch - delta.y * self.sensitivity).clamp(-MAX_PITCH, MAX_PITCH);
+    }
+
+    pub fn rotation(&self) -> Quat {
+        Quat::from_rotation_y(-self.yaw.to_radians())
+            * Quat::from_rotation_x(self.pitch.to_radians())
+    }
+
+    pub fn forward(&self) -> Vec3 {
+        self.rotation() * Vec3::NEG_Z
+    }
+
+    /// Always horizontal, since the camera doesn't roll.
+    pub fn right(&self) -> Vec3 {
+        self.rotation() * Vec3::X
+    }
+
+    /// Moves by `amount` along forward (`z`), right (`x`) and world up (`y`).
+    pub fn translate_local(&mut self, amount: Vec3) {
+        self.position += self.forward() * amount.z + self.right() * amount.x + Vec3::Y * amount.y;
+    }
+
+    /// Looks around with this frame's mouse motion and moves with WASD,
+    /// Space and Left Ctrl, `dtime` seconds after the previous update.
+    pub fn update(&mut self, input: &InputState, dtime: f32) {
+        self.look(input.mouse_delta());
+
+        let axis = |positive, negative| {
+            input.is_key_pressed(positive) as i32 as f32
+                - input.is_key_pressed(negative) as i32 as f32
+        };
+
+        let direction = Vec3::new(
+            axis(KeyCode::KeyD, KeyCode::KeyA),
+            axis(KeyCode::Space, KeyCode::ControlLeft),
+            axis(KeyCode::KeyW, KeyCode::KeyS),
+        );
+
+        self.translate_local(direction.normalize_or_zero() * self.speed * dtime);
+    }
+
+    pub fn camera(&self) -> Camera {
+        Camera {
+            position: self.position,
+            pitch: self.pitch,
+            yaw: self.yaw,
+            fov: self.fov,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pitch_is_clamped() {
+        let mut camera = FreeLookCamera::new(Vec3::ZERO);
+        camera.look(Vec2::new(0.0, -10000.0));
+
+        assert_eq!(camera.pitch(), MAX_PITCH);
+        assert!(camera.forward().y > 0.99);
+        assert!(camera.right().abs_diff_eq(Vec3::X, 1e-5));
+    }
+
+    #[test]
+    fn matches_scene_camera() {
+        let mut camera = FreeLookCamera::new(Vec3::new(1.0, 2.0, 3.0));
+        camera.look(Vec2::new(300.0, 150.0));
+        camera.translate_local(Vec3::new(0.0, 0.0, 2.0));
+
+        let (look, right) = camera.camera().forward_right();
+
+        assert!(look.abs_diff_eq(camera.forward(), 1e-5));
+        assert!(right.abs_diff_eq(camera.right(), 1e-5));
+        assert!((camera.position.distance(Vec3::new(1.0, 2.0, 3.0)) - 2.0).abs() < 1e-5);
+    }
+}
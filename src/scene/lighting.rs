@@ -0,0 +1,19 @@
+use crate::color::Color;
+
+/// Scene-wide lighting parameters applied on top of the scene's lights.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Lighting {
+    pub ambient_color: Color,
+    pub ambient_intensity: f32,
+    pub exposure: f32,
+}
+
+impl Default for Lighting {
+    fn default() -> Self {
+        Self {
+            ambient_color: Color::WHITE,
+            ambient_intensity: 0.4,
+            exposure: 1.0,
+        }
+    }
+}
@@ -1,14 +1,72 @@
 use crate::asset::AssetId;
+use crate::math::Aabb;
+use crate::render::{MaterialHandle, MeshHandle};
 use crate::scene::Node;
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct Mesh {
     mesh_id: AssetId,
+    #[serde(default)]
+    bounds: Option<Aabb>,
+    /// Uploaded copies of the model's meshes, only valid for the renderer
+    /// they came from.
+    #[serde(skip)]
+    renderables: Vec<MeshHandle>,
+    #[serde(skip)]
+    material: Option<MaterialHandle>,
 }
 
 impl Mesh {
     pub fn new(mesh_id: AssetId) -> Self {
-        Self { mesh_id }
+        Self {
+            mesh_id,
+            bounds: None,
+            renderables: Vec::new(),
+            material: None,
+        }
+    }
+
+    pub fn with_bounds(mut self, bounds: Aabb) -> Self {
+        self.bounds = Some(bounds);
+        self
+    }
+
+    pub fn with_renderable(mut self, renderable: MeshHandle) -> Self {
+        self.renderables.push(renderable);
+        self
+    }
+
+    pub fn with_material(mut self, material: MaterialHandle) -> Self {
+        self.material = Some(material);
+        self
+    }
+
+    pub fn mesh_id(&self) -> AssetId {
+        self.mesh_id
+    }
+
+    /// Local-space bounds of the mesh, if known.
+    pub fn bounds(&self) -> Option<Aabb> {
+        self.bounds
+    }
+
+    /// The meshes drawn for this node, one per mesh of the model. They're
+    /// added as the model is uploaded, see [`crate::sys::upload_loaded_meshes`].
+    pub fn renderables(&self) -> &[MeshHandle] {
+        &self.renderables
+    }
+
+    pub fn add_renderable(&mut self, renderable: MeshHandle) {
+        self.renderables.push(renderable);
+    }
+
+    /// `None` draws with [`Renderer::error_material`](crate::render::Renderer::error_material).
+    pub fn material(&self) -> Option<MaterialHandle> {
+        self.material
+    }
+
+    pub fn set_material(&mut self, material: Option<MaterialHandle>) {
+        self.material = material;
     }
 }
 
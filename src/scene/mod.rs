@@ -1,14 +1,23 @@
 use std::ops::{Deref, DerefMut};
 
 mod camera;
+mod data;
+mod free_look;
+mod lighting;
 mod mesh;
 mod node;
 mod pivot;
 mod transform;
 
+use crate::color::Color;
 use crate::core::{Arena, ArenaHandle};
+use crate::math::{ray_aabb, Aabb, Ray, Vec3};
+use crate::render::TextureHandle;
 
 pub use self::camera::*;
+pub use self::data::*;
+pub use self::free_look::*;
+pub use self::lighting::*;
 pub use self::mesh::*;
 pub use self::node::*;
 pub use self::pivot::*;
@@ -31,8 +40,14 @@ impl SceneGraph {
         self.nodes.insert(scene)
     }
 
-    pub fn set_current_scene_id(&mut self, id: SceneHandle) {
+    pub fn set_current_scene_id(&mut self, id: SceneHandle) -> Result<(), SceneError> {
+        if !self.nodes.contains(id) {
+            return Err(SceneError::InvalidScene(id));
+        }
+
         self.current_scene_id = Some(id);
+
+        Ok(())
     }
 
     pub fn current_scene_id(&self) -> SceneHandle {
@@ -44,6 +59,13 @@ impl SceneGraph {
             .expect("current scene doesn't exist")
     }
 
+    /// The current scene, or `None` if it isn't set.
+    pub fn try_current_scene(&self) -> Option<&Scene> {
+        self.current_scene_id
+            .filter(|&id| self.nodes.contains(id))
+            .and_then(|id| self.nodes.get(id))
+    }
+
     pub fn scene(&self, id: SceneHandle) -> Option<&Scene> {
         self.nodes.get(id)
     }
@@ -52,6 +74,10 @@ impl SceneGraph {
         self.nodes.iter()
     }
 
+    pub fn scene_mut(&mut self, id: SceneHandle) -> Option<&mut Scene> {
+        self.nodes.get_mut(id)
+    }
+
     pub fn scenes_mut(&mut self) -> impl Iterator<Item = (SceneHandle, &mut Scene)> {
         self.nodes.iter_mut()
     }
@@ -59,8 +85,16 @@ impl SceneGraph {
 
 pub type SceneHandle = ArenaHandle<Scene>;
 
+#[derive(thiserror::Error, Debug)]
+pub enum SceneError {
+    #[error("scene {0:?} doesn't exist")]
+    InvalidScene(SceneHandle),
+}
+
 pub struct Scene {
-    pub bg_color: u32,
+    bg_color: Color,
+    lighting: Lighting,
+    environment: Option<TextureHandle>,
     primary_camera_id: Option<NodeHandle>,
     nodes: Arena<Spatial>,
     root_node: NodeHandle,
@@ -73,14 +107,136 @@ impl Scene {
         let root_node = nodes.insert(Spatial::new(Pivot::new()));
 
         Self {
-            bg_color: 0x102030FF,
+            bg_color: Color::from_rgba_u32(0x102030FF),
+            lighting: Lighting::default(),
+            environment: None,
             primary_camera_id: None,
             nodes,
             root_node,
         }
     }
 
-    pub fn update_transform_hierarchy(&mut self) {}
+    pub fn bg_color(&self) -> Color {
+        self.bg_color
+    }
+
+    pub fn set_bg_color(&mut self, color: Color) {
+        self.bg_color = color;
+    }
+
+    pub fn lighting(&self) -> &Lighting {
+        &self.lighting
+    }
+
+    pub fn lighting_mut(&mut self) -> &mut Lighting {
+        &mut self.lighting
+    }
+
+    /// Cubemap drawn behind the scene, as returned from
+    /// [`Renderer::upload_cubemap`](crate::render::Renderer::upload_cubemap).
+    pub fn environment(&self) -> Option<TextureHandle> {
+        self.environment
+    }
+
+    pub fn set_environment(&mut self, environment: Option<TextureHandle>) {
+        self.environment = environment;
+    }
+
+    /// Recomputes world transforms of nodes whose transform, or any of
+    /// their ancestors' transforms, changed since the last update.
+    pub fn update_transform_hierarchy(&mut self) {
+        let mut stack = vec![(self.root_node, Transform::default(), false)];
+
+        while let Some((handle, parent_transform, parent_dirty)) = stack.pop() {
+            let spatial = self.spatial_mut(handle);
+            let dirty = parent_dirty || spatial.dirty;
+
+            if dirty {
+                spatial.world_transform = parent_transform * spatial.transform;
+                spatial.dirty = false;
+            }
+
+            let world_transform = spatial.world_transform;
+
+            for &child in &spatial.children {
+                stack.push((child, world_transform, dirty));
+            }
+        }
+    }
+
+    pub fn world_transform(&self, handle: NodeHandle) -> Transform {
+        self.spatial(handle).world_transform
+    }
+
+    /// `root` and all of its descendants that are visible and enabled. Hidden
+    /// or disabled nodes hide their whole subtree.
+    pub fn visible_nodes(&self, root: NodeHandle) -> Vec<NodeHandle> {
+        let mut nodes = Vec::new();
+        let mut stack = vec![root];
+
+        while let Some(handle) = stack.pop() {
+            let spatial = self.spatial(handle);
+
+            if !spatial.visible || !spatial.enabled {
+                continue;
+            }
+
+            nodes.push(handle);
+            stack.extend_from_slice(&spatial.children);
+        }
+
+        nodes
+    }
+
+    /// World-space bounds of all visible and enabled meshes, as of the last
+    /// [`Scene::update_transform_hierarchy`]. A scene without meshes has a
+    /// unit box at the origin as its bounds.
+    pub fn bounds(&self) -> Aabb {
+        self.mesh_bounds(self.root_node)
+            .unwrap_or(Aabb::new(Vec3::splat(-0.5), Vec3::splat(0.5)))
+    }
+
+    /// World-space bounds of the visible and enabled meshes under `root`,
+    /// `None` if there aren't any.
+    pub fn mesh_bounds(&self, root: NodeHandle) -> Option<Aabb> {
+        let mut bounds: Option<Aabb> = None;
+
+        for handle in self.visible_nodes(root) {
+            let spatial = self.spatial(handle);
+
+            if let Node::Mesh(mesh) = &spatial.node {
+                if let Some(mesh_bounds) = mesh.bounds() {
+                    let mesh_bounds = mesh_bounds.transformed(spatial.world_transform.matrix());
+
+                    bounds = Some(match bounds {
+                        Some(bounds) => bounds.union(&mesh_bounds),
+                        None => mesh_bounds,
+                    });
+                }
+            }
+        }
+
+        bounds
+    }
+
+    /// The closest visible mesh under `root` whose world-space bounds `ray`
+    /// hits, and the distance to them. Meshes without bounds can't be hit.
+    pub fn raycast(&self, root: NodeHandle, ray: &Ray) -> Option<(NodeHandle, f32)> {
+        self.visible_nodes(root)
+            .into_iter()
+            .filter_map(|handle| {
+                let spatial = self.spatial(handle);
+
+                let Node::Mesh(mesh) = &spatial.node else {
+                    return None;
+                };
+
+                let bounds = mesh.bounds()?.transformed(spatial.world_transform.matrix());
+
+                Some((handle, ray_aabb(ray, &bounds)?))
+            })
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+    }
 
     pub fn add_node(&mut self, node: Spatial) -> NodeHandle {
         self.nodes.insert(node)
@@ -92,7 +248,11 @@ impl Scene {
         }
 
         self.node_mut(parent).attach_child(child);
-        *self.node_mut(child).parent = Some(parent);
+
+        let node = self.node_mut(child);
+        *node.parent = Some(parent);
+        // the world transform depends on the new parent
+        *node.dirty = true;
     }
 
     pub fn unlink(&mut self, child: NodeHandle) {
@@ -100,13 +260,19 @@ impl Scene {
             self.node_mut(*previous_parent).detach_child(child);
         }
 
-        *self.node_mut(child).parent = None;
+        let node = self.node_mut(child);
+        *node.parent = None;
+        *node.dirty = true;
     }
 
     pub fn set_primary_camera_id(&mut self, id: NodeHandle) {
         self.primary_camera_id = Some(id);
     }
 
+    pub fn primary_camera_id(&self) -> Option<NodeHandle> {
+        self.primary_camera_id
+    }
+
     pub fn primary_camera(&self) -> SpatialRef {
         self.node(self.primary_camera_id.expect("primary camera not set"))
     }
@@ -119,6 +285,67 @@ impl Scene {
         self.root_node
     }
 
+    /// Looks up a node by [`NodeHandle::index`], e.g. one returned from
+    /// object picking.
+    pub fn node_at_index(&self, index: u32) -> Option<NodeHandle> {
+        self.nodes.handle_at(index)
+    }
+
+    /// Child indices leading from the root to `handle`. Unlike
+    /// [`NodeHandle::index`], this survives saving and loading the scene.
+    pub fn node_path(&self, handle: NodeHandle) -> Option<Vec<u32>> {
+        let mut path = Vec::new();
+        let mut current = handle;
+
+        while current != self.root_node {
+            let parent = self.nodes.get(current)?.parent?;
+            let siblings = &self.nodes.get(parent)?.children;
+            path.push(siblings.iter().position(|c| *c == current)? as u32);
+            current = parent;
+        }
+
+        path.reverse();
+        Some(path)
+    }
+
+    pub fn node_at_path(&self, path: &[u32]) -> Option<NodeHandle> {
+        path.iter().try_fold(self.root_node, |node, &index| {
+            self.nodes.get(node)?.children.get(index as usize).copied()
+        })
+    }
+
+    /// Every node of kind `T` along with its handle, e.g. all cameras.
+    pub fn nodes_of_type<T: NodeKind>(&self) -> impl Iterator<Item = (NodeHandle, &T)> {
+        self.nodes
+            .iter()
+            .filter_map(|(handle, spatial)| Some((handle, T::from_node(&spatial.node)?)))
+    }
+
+    /// Like [`Scene::nodes_of_type`], but skips nodes that are disabled
+    /// themselves or through an ancestor. Systems updating nodes should use
+    /// this.
+    pub fn enabled_nodes_of_type<T: NodeKind>(&self) -> impl Iterator<Item = (NodeHandle, &T)> {
+        self.nodes_of_type()
+            .filter(|(handle, _)| self.is_enabled(*handle))
+    }
+
+    /// Whether the node and all of its ancestors are enabled.
+    pub fn is_enabled(&self, handle: NodeHandle) -> bool {
+        let mut current = Some(handle);
+
+        while let Some(handle) = current {
+            let spatial = self.spatial(handle);
+
+            if !spatial.enabled {
+                return false;
+            }
+
+            current = spatial.parent;
+        }
+
+        true
+    }
+
     pub fn node(&self, handle: NodeHandle) -> SpatialRef {
         self.spatial(handle).node()
     }
@@ -250,6 +477,17 @@ impl<'a> SpatialRefMut<'a> {
         self.transform
     }
 
+    /// Hidden nodes and their descendants aren't drawn, but are still
+    /// updated.
+    pub fn set_visible(&mut self, visible: bool) {
+        *self.visible = visible;
+    }
+
+    /// Disabled nodes and their descendants are neither drawn nor updated.
+    pub fn set_enabled(&mut self, enabled: bool) {
+        *self.enabled = enabled;
+    }
+
     pub fn attach_child(&mut self, child: NodeHandle) {
         self.children.push(child);
     }
@@ -276,3 +514,182 @@ impl<'a> DerefMut for SpatialRefMut<'a> {
         self.node
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use glam::Quat;
+
+    use super::*;
+    use crate::asset::Vfs;
+
+    fn unit_mesh() -> Mesh {
+        let mesh_id = Vfs::new().acquire_asset_id_for_path("/test/mesh.obj");
+
+        Mesh::new(mesh_id).with_bounds(Aabb::new(Vec3::splat(-1.0), Vec3::splat(1.0)))
+    }
+
+    #[test]
+    fn invalid_current_scene_is_rejected() {
+        let mut sg = SceneGraph::new();
+        let mut other = SceneGraph::new();
+
+        assert!(sg.try_current_scene().is_none());
+
+        other.add_scene(Scene::new());
+        let foreign = other.add_scene(Scene::new());
+        assert!(sg.set_current_scene_id(foreign).is_err());
+        assert!(sg.try_current_scene().is_none());
+
+        let scene = sg.add_scene(Scene::new());
+        sg.set_current_scene_id(scene).unwrap();
+        assert!(sg.try_current_scene().is_some());
+    }
+
+    #[test]
+    fn nodes_of_type() {
+        let mut scene = Scene::new();
+
+        let camera = scene.add_node(Spatial::new(Camera::new()));
+        scene.add_node(Spatial::new(unit_mesh()));
+        scene.add_node(Spatial::new(unit_mesh()));
+
+        let cameras: Vec<_> = scene
+            .nodes_of_type::<Camera>()
+            .map(|(handle, _)| handle)
+            .collect();
+
+        assert_eq!(cameras, [camera]);
+        assert_eq!(scene.nodes_of_type::<Mesh>().count(), 2);
+        assert_eq!(scene.nodes_of_type::<Pivot>().count(), 1);
+    }
+
+    #[test]
+    fn disabled_parent_disables_children() {
+        let mut scene = Scene::new();
+
+        let pivot = scene.add_node(Spatial::new(Pivot::new()));
+        let camera = scene.add_node(Spatial::new(Camera::new()));
+        scene.link(scene.root(), pivot);
+        scene.link(pivot, camera);
+
+        scene.node_mut(pivot).set_visible(false);
+        assert!(scene.is_enabled(camera));
+        assert_eq!(scene.enabled_nodes_of_type::<Camera>().count(), 1);
+
+        scene.node_mut(pivot).set_enabled(false);
+        assert!(!scene.is_enabled(camera));
+        assert_eq!(scene.enabled_nodes_of_type::<Camera>().count(), 0);
+    }
+
+    #[test]
+    fn empty_scene_bounds() {
+        let scene = Scene::new();
+
+        assert_eq!(
+            scene.bounds(),
+            Aabb::new(Vec3::splat(-0.5), Vec3::splat(0.5))
+        );
+    }
+
+    #[test]
+    fn data_roundtrip() {
+        let mut scene = Scene::new();
+
+        let camera = scene.add_node(Spatial::new(Camera::new()).with_enabled(false));
+        let mesh = scene.add_node(Spatial::new(unit_mesh()).with_transform(Transform {
+            position: Vec3::new(1.0, 2.0, 3.0),
+            rotation: Quat::IDENTITY,
+        }));
+
+        scene.link(scene.root(), camera);
+        scene.link(camera, mesh);
+        scene.set_primary_camera_id(camera);
+        scene.set_bg_color(Color::WHITE);
+
+        let restored = Scene::from_data(scene.to_data());
+
+        assert_eq!(restored.bg_color(), Color::WHITE);
+
+        let camera = restored.primary_camera_id().unwrap();
+        let root = restored.node(restored.root());
+        assert_eq!(root.children.as_slice(), [camera]);
+        assert!(!*restored.node(camera).enabled);
+
+        let mesh = restored.node(camera).children[0];
+        assert_eq!(*restored.node(mesh).parent, Some(camera));
+        assert_eq!(
+            restored.world_transform(mesh).position,
+            Vec3::new(1.0, 2.0, 3.0)
+        );
+    }
+
+    #[test]
+    fn bounds_follow_hierarchy() {
+        let mut scene = Scene::new();
+
+        let pivot = scene.add_node(Spatial::new(Pivot::new()).with_transform(Transform {
+            position: Vec3::new(10.0, 0.0, 0.0),
+            rotation: Quat::from_rotation_y(std::f32::consts::FRAC_PI_2),
+        }));
+        let mesh = scene.add_node(Spatial::new(unit_mesh()).with_transform(Transform {
+            position: Vec3::new(0.0, 0.0, 5.0),
+            rotation: Quat::IDENTITY,
+        }));
+        let hidden = scene.add_node(Spatial::new(unit_mesh()).with_visible(false));
+
+        scene.link(scene.root(), pivot);
+        scene.link(pivot, mesh);
+        scene.link(scene.root(), hidden);
+        scene.update_transform_hierarchy();
+
+        let bounds = scene.bounds();
+
+        // the parent's rotation turns the child's +Z offset into +X
+        assert!(bounds.min.abs_diff_eq(Vec3::new(14.0, -1.0, -1.0), 1e-5));
+        assert!(bounds.max.abs_diff_eq(Vec3::new(16.0, 1.0, 1.0), 1e-5));
+    }
+
+    #[test]
+    fn relinking_updates_world_transform() {
+        let mut scene = Scene::new();
+
+        let moved = scene.add_node(Spatial::new(Pivot::new()).with_transform(Transform {
+            position: Vec3::new(10.0, 0.0, 0.0),
+            rotation: Quat::IDENTITY,
+        }));
+        let child = scene.add_node(Spatial::new(unit_mesh()));
+        scene.link(scene.root(), moved);
+        scene.link(scene.root(), child);
+        scene.update_transform_hierarchy();
+
+        assert_eq!(scene.world_transform(child).position, Vec3::ZERO);
+
+        scene.link(moved, child);
+        scene.update_transform_hierarchy();
+
+        assert_eq!(
+            scene.world_transform(child).position,
+            Vec3::new(10.0, 0.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn node_paths() {
+        let mut scene = Scene::new();
+
+        let pivot = scene.add_node(Spatial::new(Pivot::new()));
+        let first = scene.add_node(Spatial::new(unit_mesh()));
+        let second = scene.add_node(Spatial::new(unit_mesh()));
+        let unlinked = scene.add_node(Spatial::new(unit_mesh()));
+        scene.link(scene.root(), pivot);
+        scene.link(pivot, first);
+        scene.link(pivot, second);
+
+        assert_eq!(scene.node_path(scene.root()), Some(vec![]));
+        assert_eq!(scene.node_path(second), Some(vec![0, 1]));
+        assert_eq!(scene.node_path(unlinked), None);
+
+        assert_eq!(scene.node_at_path(&[0, 1]), Some(second));
+        assert_eq!(scene.node_at_path(&[0, 2]), None);
+    }
+}
@@ -23,12 +23,58 @@ impl Node {
         }
     }
 
+    pub fn mesh_mut(&mut self) -> &mut Mesh {
+        match self {
+            Node::Mesh(mesh) => mesh,
+            _ => panic!("node is not mesh"),
+        }
+    }
+
     pub fn camera(&self) -> &Camera {
         match self {
             Node::Camera(camera) => camera,
             _ => panic!("node is not camera"),
         }
     }
+
+    pub fn camera_mut(&mut self) -> &mut Camera {
+        match self {
+            Node::Camera(camera) => camera,
+            _ => panic!("node is not camera"),
+        }
+    }
+}
+
+/// A [`Node`] variant, see [`Scene::nodes_of_type`](super::Scene::nodes_of_type).
+pub trait NodeKind: 'static {
+    fn from_node(node: &Node) -> Option<&Self>;
+}
+
+impl NodeKind for Pivot {
+    fn from_node(node: &Node) -> Option<&Self> {
+        match node {
+            Node::Pivot(pivot) => Some(pivot),
+            _ => None,
+        }
+    }
+}
+
+impl NodeKind for Mesh {
+    fn from_node(node: &Node) -> Option<&Self> {
+        match node {
+            Node::Mesh(mesh) => Some(mesh),
+            _ => None,
+        }
+    }
+}
+
+impl NodeKind for Camera {
+    fn from_node(node: &Node) -> Option<&Self> {
+        match node {
+            Node::Camera(camera) => Some(camera),
+            _ => None,
+        }
+    }
 }
 
 pub type NodeHandle = ArenaHandle<Spatial>;
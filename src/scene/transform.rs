@@ -2,7 +2,7 @@ use std::ops::Mul;
 
 use glam::{Mat4, Quat, Vec3};
 
-#[derive(Default, Clone, Copy, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Default, Clone, Copy, serde::Serialize, serde::Deserialize)]
 pub struct Transform {
     pub position: Vec3,
     pub rotation: Quat,
@@ -19,7 +19,7 @@ impl Mul for Transform {
 
     fn mul(self, rhs: Self) -> Self::Output {
         Self {
-            position: self.position + rhs.position,
+            position: self.position + self.rotation * rhs.position,
             rotation: self.rotation * rhs.rotation,
         }
     }
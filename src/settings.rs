@@ -2,15 +2,46 @@ use std::path::PathBuf;
 
 use serde::{Deserialize, Serialize};
 
+use crate::editor::GridSettings;
+use crate::ui::{UiTessellation, UiUpdateMode};
+
 #[derive(Serialize, Deserialize)]
+#[serde(default)]
 pub struct Settings {
     pub test: String,
+    pub ui_update_mode: UiUpdateMode,
+    pub ui_tessellation: UiTessellation,
+    /// How many frames the CPU may queue ahead of the GPU. 1 gives the lowest
+    /// latency, 3 the smoothest frame pacing.
+    pub frames_in_flight: u32,
+    /// Anisotropic filtering level of textures, 1 disables it.
+    pub texture_anisotropy: u16,
+    /// Draws scene depth before color, so that hidden pixels aren't shaded.
+    pub depth_prepass: bool,
+    pub editor_grid: GridSettings,
+    /// Seconds between editor auto-saves, 0 disables auto-save.
+    pub autosave_interval_s: f32,
+    /// Updates per second while the window isn't focused, 0 pauses updating
+    /// and rendering until it's focused again.
+    pub unfocused_update_rate_hz: f32,
+    /// Threads used for background work like asset loading, 0 picks one less
+    /// than the number of CPUs.
+    pub worker_threads: usize,
 }
 
 impl Default for Settings {
     fn default() -> Self {
         Self {
             test: "12345".to_string(),
+            ui_update_mode: UiUpdateMode::default(),
+            ui_tessellation: UiTessellation::default(),
+            frames_in_flight: 2,
+            texture_anisotropy: 1,
+            depth_prepass: false,
+            editor_grid: GridSettings::default(),
+            autosave_interval_s: 60.0,
+            unfocused_update_rate_hz: 10.0,
+            worker_threads: 0,
         }
     }
 }
@@ -23,6 +54,17 @@ impl Settings {
             .unwrap_or_default()
     }
 
+    /// [`Settings::worker_threads`] with 0 resolved, always at least 1.
+    pub fn worker_thread_count(&self) -> usize {
+        if self.worker_threads > 0 {
+            return self.worker_threads;
+        }
+
+        std::thread::available_parallelism()
+            .map_or(1, |n| n.get() - 1)
+            .max(1)
+    }
+
     pub fn save(&self) {
         let data = serde_json::to_string_pretty(self).unwrap();
 
@@ -1,13 +1,38 @@
-use crate::core::{Res, ResMut};
+use std::time::SystemTime;
+
+use crate::asset::{self, AssetId};
+use crate::core::{Events, Res, ResMut};
+use crate::input::InputState;
+use crate::loader::MeshLoaded;
+use crate::profile::FrameProfiler;
 use crate::render::PreparedUi;
-use crate::render::{Extent2D, Renderer};
-use crate::scene::SceneGraph;
-use crate::ui::Ui;
+use crate::render::{DebugDraw, Extent2D, Renderer, ViewDesc};
+use crate::scene::{Mesh, SceneGraph};
+use crate::settings::Settings;
+use crate::ui::{Ui, UiUpdateMode};
+use glam::Vec2;
+use tracing::{error, info};
+use winit::keyboard::KeyCode;
 use winit::window::Window;
 
-pub fn prepare_ui(window: Res<Window>, mut ui: ResMut<Ui>, mut prepared_ui: ResMut<PreparedUi>) {
-    *prepared_ui = ui.finish_frame(&window);
-    ui.begin_frame(&window);
+pub fn prepare_ui(
+    window: Res<Window>,
+    settings: Res<Settings>,
+    mut ui: ResMut<Ui>,
+    mut prepared_ui: ResMut<PreparedUi>,
+) {
+    if ui.is_frame_active() {
+        ui.set_tessellation(&settings.ui_tessellation);
+        *prepared_ui = ui.finish_frame(&window);
+    } else {
+        // previous shapes are reused as is, but their texture updates were
+        // already applied
+        prepared_ui.textures_delta.clear();
+    }
+
+    if settings.ui_update_mode == UiUpdateMode::Continuous || ui.needs_update() {
+        ui.begin_frame(&window);
+    }
 }
 
 pub fn update_transform_hierarchy(mut sg: ResMut<SceneGraph>) {
@@ -16,6 +41,63 @@ pub fn update_transform_hierarchy(mut sg: ResMut<SceneGraph>) {
     }
 }
 
+/// Hands this frame's debug lines to the renderer and clears them. Should run
+/// after systems that draw debug geometry and before scenes are rendered.
+pub fn upload_debug_draw(mut debug_draw: ResMut<DebugDraw>, mut renderer: ResMut<Renderer>) {
+    renderer.upload_debug_draw(&debug_draw);
+    debug_draw.clear();
+}
+
+/// Uploads meshes as the loader imports them and adds them to every mesh node
+/// of their model.
+pub fn upload_loaded_meshes(
+    loaded: Events<MeshLoaded>,
+    mut renderer: ResMut<Renderer>,
+    mut sg: ResMut<SceneGraph>,
+) {
+    for MeshLoaded { model, mesh } in loaded.iter() {
+        attach_loaded_mesh(&mut renderer, &mut sg, *model, mesh);
+    }
+}
+
+fn attach_loaded_mesh(
+    renderer: &mut Renderer,
+    sg: &mut SceneGraph,
+    model: AssetId,
+    mesh: &asset::Mesh,
+) {
+    let mut targets = Vec::new();
+
+    for (scene_id, scene) in sg.scenes() {
+        targets.extend(
+            scene
+                .nodes_of_type::<Mesh>()
+                .filter(|(_, node)| node.mesh_id() == model)
+                .map(|(node, _)| (scene_id, node)),
+        );
+    }
+
+    if targets.is_empty() {
+        return;
+    }
+
+    let renderable = renderer.upload_mesh(mesh);
+
+    for (i, (scene_id, node)) in targets.into_iter().enumerate() {
+        // the upload holds the first node's reference
+        if i > 0 {
+            renderer.retain_mesh(renderable);
+        }
+
+        let scene = sg.scene_mut(scene_id).unwrap();
+        scene
+            .node_mut(node)
+            .node
+            .mesh_mut()
+            .add_renderable(renderable);
+    }
+}
+
 pub fn render_primary_scene(
     window: Res<Window>,
     prepared_ui: Res<PreparedUi>,
@@ -29,13 +111,112 @@ pub fn render_primary_scene(
         height: window_size.height,
     };
 
-    renderer.render(
-        sg.current_scene()
-            .primary_camera()
-            .camera()
-            .view_projection(extent.aspect_ratio()),
-        sg.current_scene(),
-        &prepared_ui,
-        extent,
-    );
+    let scene = sg.current_scene();
+
+    // the UI, including the editor, is drawn over the scene
+    if let Some(camera_id) = scene.primary_camera_id() {
+        let view = ViewDesc {
+            camera: scene.node(camera_id).node.camera(),
+            root: scene.root(),
+            origin: Vec2::ZERO,
+            size: Vec2::ONE,
+        };
+
+        renderer.render_views(scene, &[view]);
+    }
+
+    if let Err(err) = renderer.render(scene, &prepared_ui, extent) {
+        error!("{err}");
+    }
+}
+
+/// Saves a screenshot to the working directory when F12 is pressed.
+pub fn capture_screenshot(input: Res<InputState>, mut renderer: ResMut<Renderer>) {
+    if input.is_key_just_pressed(KeyCode::F12) {
+        renderer.request_capture();
+    }
+
+    let Some(image) = renderer.take_capture() else {
+        return;
+    };
+
+    let timestamp = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis();
+    let path = format!("screenshot-{timestamp}.png");
+
+    match image.save(&path) {
+        Ok(()) => info!(path, "saved screenshot"),
+        Err(err) => error!(path, %err, "failed to save screenshot"),
+    }
+}
+
+/// Profiles the next frame when F9 is pressed, see [`FrameProfiler`].
+pub fn capture_frame_profile(input: Res<InputState>, mut profiler: ResMut<FrameProfiler>) {
+    if input.is_key_just_pressed(KeyCode::F9) {
+        profiler.request();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::Vec3;
+
+    use super::*;
+    use crate::asset::{ShaderStage, Vertex, Vfs};
+    use crate::loader::{ShaderBytecode, ShaderCompiler};
+    use crate::scene::{Scene, Spatial};
+
+    fn triangle() -> asset::Mesh {
+        let mut mesh = asset::Mesh::new();
+
+        for position in [Vec3::ZERO, Vec3::X, Vec3::Y] {
+            mesh.add_vertex(Vertex {
+                position,
+                normal: Vec3::Z,
+                texcoord: Vec2::ZERO,
+            });
+        }
+
+        mesh.add_triangle([0, 1, 2]);
+        mesh
+    }
+
+    #[test]
+    #[ignore = "needs a GPU and dxcompiler"]
+    fn shared_mesh_outlives_one_unload() {
+        let compiler = ShaderCompiler::new();
+        let compile = |stage| compiler.compile_error_shader(stage, ShaderBytecode::SpirV);
+
+        let mut renderer =
+            Renderer::headless(compile(ShaderStage::Vertex), compile(ShaderStage::Fragment));
+
+        let model = Vfs::new().acquire_asset_id_for_path("/test/model.obj");
+
+        let mut scene = Scene::new();
+        let first = scene.add_node(Spatial::new(Mesh::new(model)));
+        let second = scene.add_node(Spatial::new(Mesh::new(model)));
+
+        let mut sg = SceneGraph::new();
+        let scene_id = sg.add_scene(scene);
+
+        // a model with two meshes
+        attach_loaded_mesh(&mut renderer, &mut sg, model, &triangle());
+        attach_loaded_mesh(&mut renderer, &mut sg, model, &triangle());
+
+        let scene = sg.scene(scene_id).unwrap();
+        let renderables = |node| scene.node(node).node.mesh().renderables().to_vec();
+
+        assert_eq!(renderables(first).len(), 2);
+        assert_eq!(renderables(first), renderables(second));
+
+        for handle in renderables(first) {
+            renderer.unload_mesh(handle);
+            assert!(renderer.is_mesh_loaded(handle));
+
+            renderer.unload_mesh(handle);
+            assert!(!renderer.is_mesh_loaded(handle));
+        }
+    }
 }
@@ -2,9 +2,15 @@ use std::time::{Duration, Instant};
 
 use crate::core::ResMut;
 
+/// Number of frames kept for [`Time`] statistics.
+pub const FRAME_HISTORY: usize = 240;
+
 pub struct Time {
     start_of_previous_frame: Instant,
     dtime: Duration,
+    history: [f64; FRAME_HISTORY],
+    history_len: usize,
+    history_next: usize,
 }
 
 impl Time {
@@ -12,6 +18,9 @@ impl Time {
         Self {
             start_of_previous_frame: Instant::now(),
             dtime: Duration::ZERO,
+            history: [0.0; FRAME_HISTORY],
+            history_len: 0,
+            history_next: 0,
         }
     }
 
@@ -31,9 +40,93 @@ impl Time {
         let now = Instant::now();
         self.dtime = now - self.start_of_previous_frame;
         self.start_of_previous_frame = now;
+
+        self.push_frame_time(self.dtime_ms());
+    }
+
+    fn push_frame_time(&mut self, ms: f64) {
+        self.history[self.history_next] = ms;
+        self.history_next = (self.history_next + 1) % FRAME_HISTORY;
+        self.history_len = (self.history_len + 1).min(FRAME_HISTORY);
+    }
+
+    /// Frame times in milliseconds of the last [`FRAME_HISTORY`] frames, in
+    /// no particular order.
+    fn frame_times(&self) -> &[f64] {
+        &self.history[..self.history_len]
+    }
+
+    pub fn avg_ms(&self) -> f64 {
+        if self.history_len == 0 {
+            return 0.0;
+        }
+
+        self.frame_times().iter().sum::<f64>() / self.history_len as f64
+    }
+
+    pub fn min_ms(&self) -> f64 {
+        self.frame_times()
+            .iter()
+            .copied()
+            .reduce(f64::min)
+            .unwrap_or(0.0)
+    }
+
+    pub fn max_ms(&self) -> f64 {
+        self.frame_times()
+            .iter()
+            .copied()
+            .reduce(f64::max)
+            .unwrap_or(0.0)
+    }
+
+    pub fn p95_ms(&self) -> f64 {
+        self.percentile_ms(0.95)
+    }
+
+    pub fn p99_ms(&self) -> f64 {
+        self.percentile_ms(0.99)
+    }
+
+    /// Nearest-rank percentile of recent frame times, `p` in `[0, 1]`.
+    pub fn percentile_ms(&self, p: f64) -> f64 {
+        if self.history_len == 0 {
+            return 0.0;
+        }
+
+        let mut sorted = self.history;
+        let sorted = &mut sorted[..self.history_len];
+        sorted.sort_unstable_by(f64::total_cmp);
+
+        let rank = (p.clamp(0.0, 1.0) * self.history_len as f64).ceil() as usize;
+
+        sorted[rank.saturating_sub(1)]
     }
 }
 
 pub fn advance(mut time: ResMut<Time>) {
     time.advance_frame();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frame_time_statistics() {
+        let mut time = Time::new();
+
+        // only the last FRAME_HISTORY frames count
+        time.push_frame_time(1000.0);
+
+        for ms in 1..=FRAME_HISTORY {
+            time.push_frame_time(ms as f64);
+        }
+
+        assert_eq!(time.min_ms(), 1.0);
+        assert_eq!(time.max_ms(), FRAME_HISTORY as f64);
+        assert_eq!(time.avg_ms(), (FRAME_HISTORY + 1) as f64 / 2.0);
+        assert_eq!(time.p95_ms(), 228.0);
+        assert_eq!(time.percentile_ms(1.0), FRAME_HISTORY as f64);
+    }
+}
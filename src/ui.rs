@@ -1,12 +1,56 @@
+use std::time::{Duration, Instant};
+
 use crate::render::PreparedUi;
-use egui::epaint::Shadow;
-use egui::{vec2, Align2, Color32, Context, Frame, Margin, RichText, Rounding, Stroke, Vec2};
+use egui::epaint::{Shadow, TessellationOptions};
+use egui::{
+    vec2, Align2, Color32, Context, Frame, Margin, RichText, Rounding, Stroke, Vec2, ViewportId,
+};
+use serde::{Deserialize, Serialize};
 use winit::event::WindowEvent;
 use winit::window::Window;
 
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum UiUpdateMode {
+    /// Lay out the UI every frame.
+    #[default]
+    Continuous,
+
+    /// Lay out the UI only after input or when egui asks for a repaint,
+    /// otherwise keep presenting the previous [`PreparedUi`].
+    Reactive,
+}
+
+/// How egui turns shapes into triangles. The defaults match egui's.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UiTessellation {
+    /// Anti-alias edges with a thin feathered band. Off gives crisper but
+    /// aliased edges and slightly fewer vertices.
+    pub feathering: bool,
+    pub feathering_size_px: f32,
+    /// Maximum distance in points between a bezier curve and its
+    /// tessellation. Higher values produce fewer vertices.
+    pub bezier_tolerance: f32,
+}
+
+impl Default for UiTessellation {
+    fn default() -> Self {
+        let options = TessellationOptions::default();
+
+        Self {
+            feathering: options.feathering,
+            feathering_size_px: options.feathering_size_in_pixels,
+            bezier_tolerance: options.bezier_tolerance,
+        }
+    }
+}
+
 pub struct Ui {
     ctx: egui::Context,
-    // winit_state: egui_winit::State,
+    winit_state: egui_winit::State,
+    frame_active: bool,
+    input_pending: bool,
+    repaint_deadline: Option<Instant>,
 }
 
 // #[cfg(windows)]
@@ -18,8 +62,17 @@ pub struct Ui {
 impl Ui {
     pub fn new(window: &Window) -> Self {
         let ctx = egui::Context::default();
-        // let winit_state =
-        //     egui_winit::State::new(ctx.clone(), ctx.viewport_id(), window, None, None);
+
+        // egui_winit converts physical window coordinates into logical points
+        // using the window scale factor, which egui's hit-testing expects
+        let winit_state = egui_winit::State::new(
+            ctx.clone(),
+            ctx.viewport_id(),
+            window,
+            Some(window.scale_factor() as f32),
+            window.theme(),
+            None,
+        );
 
         // let main = load_font();
 
@@ -69,19 +122,50 @@ impl Ui {
             style.visuals.widgets.inactive.fg_stroke.color = Color32::from_rgb(0xD6, 0xD6, 0xD6);
         });
 
-        Self { ctx }
+        Self {
+            ctx,
+            winit_state,
+            frame_active: false,
+            input_pending: false,
+            repaint_deadline: None,
+        }
     }
 
     pub fn on_event(&mut self, window: &Window, event: &WindowEvent) {
-        // let _ = self.winit_state.on_window_event(window, event);
+        let response = self.winit_state.on_window_event(window, event);
+
+        if response.repaint {
+            self.input_pending = true;
+        }
     }
 
     pub fn begin_frame(&mut self, window: &Window) {
-        // let input = self.winit_state.take_egui_input(window);
-        self.ctx.begin_pass(egui::RawInput::default());
+        let input = self.winit_state.take_egui_input(window);
+        self.ctx.begin_pass(input);
+        self.frame_active = true;
+        self.input_pending = false;
+    }
+
+    /// Whether UI can be built right now, i.e. `begin_frame` was called
+    /// and `finish_frame` wasn't yet.
+    pub fn is_frame_active(&self) -> bool {
+        self.frame_active
+    }
+
+    /// Whether the previous layout is stale because of new input or because
+    /// egui requested a repaint (e.g. for an animation).
+    pub fn needs_update(&self) -> bool {
+        self.input_pending
+            || self
+                .repaint_deadline
+                .is_some_and(|deadline| Instant::now() >= deadline)
     }
 
     pub fn status_bar(&self, data: &[(&str, &str)]) {
+        if !self.frame_active {
+            return;
+        }
+
         egui::Window::new("--videoland-status-bar")
             .anchor(Align2::LEFT_TOP, vec2(10.0, 10.0))
             .title_bar(false)
@@ -102,26 +186,55 @@ impl Ui {
             });
     }
 
+    /// Takes effect from the next [`Ui::finish_frame`].
+    pub fn set_tessellation(&self, tessellation: &UiTessellation) {
+        self.ctx.tessellation_options_mut(|options| {
+            options.feathering = tessellation.feathering;
+            options.feathering_size_in_pixels = tessellation.feathering_size_px;
+            options.bezier_tolerance = tessellation.bezier_tolerance;
+        });
+    }
+
     pub fn finish_frame(&mut self, window: &Window) -> PreparedUi {
         let output = self.ctx.end_pass();
+        self.frame_active = false;
 
-        // self.winit_state
-        // .handle_platform_output(window, output.platform_output);
+        let repaint_delay = output
+            .viewport_output
+            .get(&ViewportId::ROOT)
+            .map_or(Duration::MAX, |viewport| viewport.repaint_delay);
+        self.repaint_deadline = Instant::now().checked_add(repaint_delay);
 
-        let shapes = self
-            .ctx
-            .tessellate(output.shapes, window.scale_factor() as f32);
+        self.winit_state
+            .handle_platform_output(window, output.platform_output);
+
+        // shapes are in points, pixels_per_point includes both the window
+        // scale factor and egui's zoom
+        let pixels_per_point = output.pixels_per_point;
+        let shapes = self.ctx.tessellate(output.shapes, pixels_per_point);
         let textures_delta = output.textures_delta;
 
         PreparedUi {
             shapes,
             textures_delta,
+            pixels_per_point,
         }
     }
 
     pub fn ctx(&self) -> &Context {
         &self.ctx
     }
+
+    /// Whether the pointer is over UI or UI is being dragged, so the pointer
+    /// shouldn't be used for anything else.
+    pub fn wants_pointer_input(&self) -> bool {
+        self.ctx.wants_pointer_input()
+    }
+
+    /// Whether UI has keyboard focus, e.g. a text field is being edited.
+    pub fn wants_keyboard_input(&self) -> bool {
+        self.ctx.wants_keyboard_input()
+    }
 }
 
 pub fn status_data(ui: &mut egui::Ui, data: &[(&str, &str)]) {